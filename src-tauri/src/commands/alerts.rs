@@ -0,0 +1,40 @@
+use crate::types::alerts::{Alert, AlertRule};
+use crate::AppData;
+use std::sync::Mutex;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_alert_rules<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+) -> Result<Vec<AlertRule>, String> {
+    Ok(app_data.lock().unwrap().alerts.rules().to_vec())
+}
+
+// replaces all alert rules, the rules are persisted to the app data dir
+#[tauri::command]
+pub async fn set_alert_rules<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+    rules: Vec<AlertRule>,
+) -> Result<(), String> {
+    app_data.lock().unwrap().alerts.set_rules(rules)
+}
+
+// the alerts that fired or resolved recently, oldest first
+#[tauri::command]
+pub async fn get_alert_history<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+) -> Result<Vec<Alert>, String> {
+    Ok(app_data
+        .lock()
+        .unwrap()
+        .alerts
+        .history()
+        .iter()
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub async fn clear_alert_history<'l>(app_data: State<'l, Mutex<AppData>>) -> Result<(), String> {
+    app_data.lock().unwrap().alerts.clear_history()
+}
@@ -0,0 +1,12 @@
+use crate::commands::config::load_app_config;
+use crate::types::checkpoint::Checkpoint;
+use crate::utils::checkpoint::discover_checkpoint;
+use tauri::AppHandle;
+
+// finds a recent finalized block root that the checkpoint sync endpoints agree on. it
+// isn't applied here, the user gets to see its slot and age before saving it
+#[tauri::command]
+pub async fn discover_trusted_block_root(app: AppHandle) -> Result<Checkpoint, String> {
+    let config = load_app_config(&app)?;
+    discover_checkpoint(&config.checkpoint_sync).await
+}
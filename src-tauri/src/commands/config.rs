@@ -0,0 +1,128 @@
+use crate::commands::exporter::apply_exporter_config;
+use crate::types::app_config::{migrate, AppConfig, CONFIG_VERSION};
+use crate::types::config::{ConfigError, TrinConfig};
+use crate::utils::storage::{available_space, default_trin_data_dir, dir_size};
+use log::info;
+use serde_json::Map;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri::Manager;
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_store::StoreExt;
+
+const CONFIG_STORE: &str = "config.json";
+
+#[tauri::command]
+pub async fn get_config(app: AppHandle) -> Result<AppConfig, String> {
+    load_app_config(&app)
+}
+
+// saves the config, and applies the settings that take effect right away. the trin
+// config is only used the next time trin is launched
+#[tauri::command]
+pub async fn set_config(app: AppHandle, config: AppConfig) -> Result<(), String> {
+    let previous = load_app_config(&app)?;
+    // profiles are managed by their own commands, editing the config edits the active one
+    let with_profiles = |stored: &AppConfig| {
+        let mut config = AppConfig {
+            profiles: stored.profiles.clone(),
+            active_profile: stored.active_profile.clone(),
+            ..config.clone()
+        };
+        config.sync_active_profile().map(|_| config)
+    };
+    // the config is checked before anything is applied
+    with_profiles(&previous)?;
+    if config.autostart != previous.autostart {
+        let autolaunch = app.autolaunch();
+        let result = match config.autostart {
+            true => autolaunch.enable(),
+            false => autolaunch.disable(),
+        };
+        result.map_err(|e| e.to_string())?;
+    }
+    if config.metrics_exporter != previous.metrics_exporter {
+        apply_exporter_config(&app, config.metrics_exporter).await?;
+    }
+    update_app_config(&app, |stored| {
+        *stored = with_profiles(stored)?;
+        Ok(())
+    })
+}
+
+// config.json is read, changed and written back by several commands, which could
+// otherwise overwrite each other's changes
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+// reads config.json, migrating it first if it was written by an older version of the app
+pub fn load_app_config(app: &AppHandle) -> Result<AppConfig, String> {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    read_app_config(app)
+}
+
+// changes the config and saves it, without letting any other change in between. nothing
+// is saved if `update` fails
+pub fn update_app_config<T>(
+    app: &AppHandle,
+    update: impl FnOnce(&mut AppConfig) -> Result<T, String>,
+) -> Result<T, String> {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    let mut config = read_app_config(app)?;
+    let result = update(&mut config)?;
+    let store = app.store(CONFIG_STORE).map_err(|e| e.to_string())?;
+    for (key, value) in config.to_entries()? {
+        store.set(key, value);
+    }
+    store.save().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+fn read_app_config(app: &AppHandle) -> Result<AppConfig, String> {
+    let store = app.store(CONFIG_STORE).map_err(|e| e.to_string())?;
+    let mut entries: Map<_, _> = store.entries().into_iter().collect();
+    if migrate(&mut entries) {
+        info!("migrating {CONFIG_STORE} to version {CONFIG_VERSION}");
+        for (key, value) in &entries {
+            store.set(key.clone(), value.clone());
+        }
+        store.save().map_err(|e| e.to_string())?;
+    }
+    AppConfig::from_entries(entries)
+}
+
+// checks every field of the config, an empty list means that trin can be launched with it
+#[tauri::command]
+pub async fn validate_config(
+    app: AppHandle,
+    trin_config: TrinConfig,
+) -> Result<Vec<ConfigError>, String> {
+    Ok(validate_trin_config(&app, &trin_config).await)
+}
+
+// validates the config on its own, and checks that its storage fits on the disk
+pub async fn validate_trin_config(app: &AppHandle, trin_config: &TrinConfig) -> Vec<ConfigError> {
+    let mut errors = trin_config.validate();
+    let storage_is_valid = errors.iter().all(|error| error.field != "storage");
+    let data_dir = trin_data_dir(app, trin_config);
+    if let (true, Some(data_dir)) = (storage_is_valid, data_dir) {
+        // walking the data dir can take a moment
+        let space = tauri::async_runtime::spawn_blocking(move || {
+            let used = dir_size(&data_dir).unwrap_or_default();
+            (available_space(&data_dir), used)
+        })
+        .await;
+        if let Ok((available, used)) = space {
+            errors.extend(trin_config.check_disk_space(available, used));
+        }
+    }
+    errors
+}
+
+// where trin stores its database when launched with this config
+pub fn trin_data_dir(app: &AppHandle, trin_config: &TrinConfig) -> Option<PathBuf> {
+    match &trin_config.dataDir {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => default_trin_data_dir(app.path().local_data_dir().ok()),
+    }
+}
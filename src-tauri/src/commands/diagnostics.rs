@@ -0,0 +1,136 @@
+use crate::commands::config::load_app_config;
+use crate::types::crash_report::CrashReport;
+use crate::utils::diagnostics::{redact_secrets, DiagnosticsBundle, SystemInfo};
+use crate::AppData;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri::Manager;
+use tauri_plugin_shell::ShellExt;
+
+// the most of each app log file that is included in the bundle
+const MAX_APP_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+// writes everything needed to debug a misbehaving node into a single .tar.gz archive.
+// without a path, the archive is written to the app data dir. returns the archive's path
+#[tauri::command]
+pub async fn export_diagnostics(
+    app: AppHandle,
+    path: Option<String>,
+    anonymize: bool,
+) -> Result<String, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let dir_name = format!("trin-desktop-diagnostics-{timestamp}");
+    let path = path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_dir.join(format!("{dir_name}.tar.gz")));
+
+    let app_config = load_app_config(&app).inspect_err(|e| warn!("unable to load the config: {e}"));
+    let mut bundle = DiagnosticsBundle::default();
+    {
+        let state = app.state::<Mutex<AppData>>();
+        let state = state.lock().unwrap();
+        bundle.add("trin.log", state.trin_logs.to_text());
+        // the config trin is running with, or the one it will be launched with
+        let trin_config = state
+            .trin_config
+            .clone()
+            .or_else(|| app_config.as_ref().ok().map(|config| config.trin.clone()));
+        add_config(&mut bundle, "trin_config.json", &trin_config)?;
+        bundle.add_json(
+            "node_status.json",
+            &json!({
+                "state": state.node_state,
+                "readiness": state.readiness,
+                "stats": state.node_stats,
+                "appliedLimits": state.applied_limits,
+            }),
+        )?;
+    }
+
+    if let Ok(app_config) = &app_config {
+        add_config(&mut bundle, "app_config.json", app_config)?;
+    }
+    bundle.add_json(
+        "versions.json",
+        &json!({
+            "app": app.package_info().version.to_string(),
+            "trin": trin_version(&app).await,
+        }),
+    )?;
+    let log_dir = app.path().app_log_dir();
+
+    // reading the files, collecting the system info and compressing the archive
+    // are slow, so they're done on a blocking thread
+    tauri::async_runtime::spawn_blocking(move || -> Result<String, String> {
+        bundle.add_json("system.json", &SystemInfo::collect())?;
+        if let Some(report) = CrashReport::load(&data_dir)? {
+            bundle.add_json("crash_report.json", &report)?;
+        }
+
+        // the stats history & app logs are added as they are on disk
+        let stats_history = data_dir.join("stats_history");
+        add_dir(&mut bundle, &stats_history, "stats_history", u64::MAX);
+        match log_dir {
+            Ok(log_dir) => add_dir(&mut bundle, &log_dir, "app_logs", MAX_APP_LOG_BYTES),
+            Err(e) => warn!("unable to find the app log dir: {e}"),
+        }
+
+        if anonymize {
+            bundle.anonymize();
+        }
+        bundle.write(&path, &dir_name)?;
+        info!("diagnostics exported to {}", path.display());
+        Ok(path.display().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// the output of `trin --version`, this also works while trin isn't running
+async fn trin_version(app: &AppHandle) -> Option<String> {
+    let output = app
+        .shell()
+        .sidecar("trin")
+        .ok()?
+        .args(["--version"])
+        .output()
+        .await
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// configs are added with their credentials redacted, even if the bundle isn't anonymized
+fn add_config(
+    bundle: &mut DiagnosticsBundle,
+    name: &str,
+    config: &impl Serialize,
+) -> Result<(), String> {
+    let mut config = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    redact_secrets(&mut config);
+    bundle.add_json(name, &config)
+}
+
+fn add_dir(bundle: &mut DiagnosticsBundle, dir: &Path, name: &str, max_bytes: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let file_name = format!("{name}/{}", entry.file_name().to_string_lossy());
+        if let Err(e) = bundle.add_file_tail(&file_name, &path, max_bytes) {
+            warn!("unable to add {} to diagnostics: {e}", path.display());
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::commands::config::{load_app_config, update_app_config};
+use crate::types::exporter::{render, ExporterConfig, SupervisorMetrics};
+use crate::utils::exporter::{start_exporter, MetricsExporter};
+use crate::AppData;
+use log::warn;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri::Manager;
+
+#[tauri::command]
+pub async fn get_exporter_config(app: AppHandle) -> Result<ExporterConfig, String> {
+    Ok(load_app_config(&app)?.metrics_exporter)
+}
+
+// (re)starts or stops the exporter, the config is only saved if it could be applied
+#[tauri::command]
+pub async fn set_exporter_config(app: AppHandle, config: ExporterConfig) -> Result<(), String> {
+    apply_exporter_config(&app, config).await?;
+    update_app_config(&app, |app_config| {
+        app_config.metrics_exporter = config;
+        Ok(())
+    })
+}
+
+// starts the exporter on startup, if the user enabled it
+pub async fn restore_exporter(app: AppHandle) {
+    let config = match load_app_config(&app) {
+        Ok(config) => config.metrics_exporter,
+        Err(e) => {
+            warn!("unable to load the config: {e}");
+            return;
+        }
+    };
+    if !config.enabled {
+        return;
+    }
+    if let Err(e) = apply_exporter_config(&app, config).await {
+        warn!("{e}");
+    }
+}
+
+pub async fn apply_exporter_config(app: &AppHandle, config: ExporterConfig) -> Result<(), String> {
+    let state = app.state::<Mutex<AppData>>();
+    let running_port = state
+        .lock()
+        .unwrap()
+        .exporter
+        .as_ref()
+        .map(MetricsExporter::port);
+    if config.enabled && running_port == Some(config.port) {
+        return Ok(());
+    }
+    // the new port is bound before the running exporter is stopped, so that it keeps
+    // serving if the port can't be used
+    let exporter = match config.enabled {
+        true => {
+            let app_clone = app.clone();
+            Some(start_exporter(config.port, move || render_metrics(&app_clone)).await?)
+        }
+        false => None,
+    };
+    let running = std::mem::replace(&mut state.lock().unwrap().exporter, exporter);
+    if let Some(running) = running {
+        running.stop().await;
+    }
+    Ok(())
+}
+
+fn render_metrics(app: &AppHandle) -> String {
+    let state = app.state::<Mutex<AppData>>();
+    let state = state.lock().unwrap();
+    let supervisor = SupervisorMetrics {
+        running: state.node_state.is_up(),
+        uptime_secs: state
+            .running_since
+            .map(|since| since.elapsed().as_secs())
+            .unwrap_or_default(),
+        restarts: state.supervisor.restarts(),
+        crashes: state.supervisor.total_crashes(),
+        recent_crashes: state.supervisor.recent_crashes(),
+    };
+    render(&state.node_stats, &supervisor)
+}
@@ -1,3 +1,9 @@
+pub mod alerts;
 pub mod beacon;
+pub mod checkpoint;
+pub mod config;
+pub mod diagnostics;
 pub mod eth;
-pub mod trin;
+pub mod exporter;
+pub mod profiles;
+pub mod trin;
\ No newline at end of file
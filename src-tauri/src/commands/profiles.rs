@@ -0,0 +1,101 @@
+use crate::commands::config::{load_app_config, update_app_config, validate_trin_config};
+use crate::commands::trin::{needs_relaunch, relaunch_trin};
+use crate::types::app_config::{profile_dir_name, ConfigProfile, ProfileList};
+use crate::types::config::{describe_errors, TrinConfig};
+use tauri::AppHandle;
+use tauri::Manager;
+
+#[tauri::command]
+pub async fn get_profiles(app: AppHandle) -> Result<ProfileList, String> {
+    let config = load_app_config(&app)?;
+    Ok(ProfileList {
+        active: config.active_profile,
+        profiles: config.profiles,
+    })
+}
+
+// adds a profile, without a data dir it gets one of its own
+#[tauri::command]
+pub async fn create_profile(
+    app: AppHandle,
+    name: String,
+    trin_config: TrinConfig,
+) -> Result<ConfigProfile, String> {
+    add_profile(&app, name, trin_config).await
+}
+
+// copies an existing profile, the copy gets its own data dir so that the two
+// profiles don't share a database
+#[tauri::command]
+pub async fn clone_profile(
+    app: AppHandle,
+    from: String,
+    name: String,
+) -> Result<ConfigProfile, String> {
+    let config = load_app_config(&app)?;
+    let mut trin_config = config
+        .profile(&from)
+        .ok_or_else(|| format!("there is no profile named {from:?}"))?
+        .config
+        .clone();
+    trin_config.dataDir = None;
+    add_profile(&app, name, trin_config).await
+}
+
+// the profile's data dir is left on disk, deleting it would throw away trin's database
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    update_app_config(&app, |config| config.remove_profile(&name).map(|_| ()))
+}
+
+// switches to another profile, restarting trin with it if trin is running. the switch
+// is saved first, and undone if trin couldn't be restarted with the profile
+#[tauri::command]
+pub async fn activate_profile(app: AppHandle, name: String) -> Result<(), String> {
+    let trin_config = load_app_config(&app)?
+        .profile(&name)
+        .ok_or_else(|| format!("there is no profile named {name:?}"))?
+        .config
+        .clone();
+    let restart = needs_relaunch(&app, &trin_config).await?;
+    let previous = update_app_config(&app, |config| {
+        let previous = config.active_profile.clone();
+        config.activate_profile(&name)?;
+        Ok(previous)
+    })?;
+    if !restart {
+        return Ok(());
+    }
+    if let Err(e) = relaunch_trin(&app, trin_config).await {
+        update_app_config(&app, |config| config.activate_profile(&previous))?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+// the profile's config is checked up front, rather than when it is first launched
+async fn add_profile(
+    app: &AppHandle,
+    name: String,
+    mut trin_config: TrinConfig,
+) -> Result<ConfigProfile, String> {
+    if trin_config.dataDir.is_none() {
+        let dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())?
+            .join("profiles")
+            .join(profile_dir_name(&name));
+        trin_config.dataDir = Some(dir.display().to_string());
+    }
+    let errors = validate_trin_config(app, &trin_config).await;
+    if !errors.is_empty() {
+        return Err(describe_errors(&errors));
+    }
+    let profile = ConfigProfile {
+        name: name.trim().to_string(),
+        config: trin_config,
+    };
+    update_app_config(app, |config| config.add_profile(profile.clone()))?;
+    Ok(profile)
+}
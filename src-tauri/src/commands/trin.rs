@@ -1,90 +1,286 @@
 use crate::commands::beacon::{portal_beaconFinalityUpdate, portal_beaconOptimisticUpdate};
-use crate::types::config::TrinConfig;
-use crate::types::node::SubnetworkDataLog;
-use crate::utils::node_rpc::check_trin_status;
+use crate::commands::config::{load_app_config, trin_data_dir, validate_trin_config};
+use crate::types::activity::ActivitySnapshot;
+use crate::types::alerts::Alert;
+use crate::types::config::{describe_errors, OrphanPolicy, Subnetwork, TrinConfig};
+use crate::types::crash_report::{describe_exit, CrashReport};
+use crate::types::limits::AppliedLimits;
+use crate::types::log_buffer::{LogPage, LogQuery, LogRecord, LogStream};
+use crate::types::log_parser::{LogLine, LogParsers};
+use crate::types::metrics::StatsSource;
+use crate::types::node_state::{NodeState, NodeStateEvent, NodeStatus};
+use crate::types::pid_file::PidFile;
+use crate::types::ports::PortConflict;
+use crate::types::readiness::{ReadinessEvent, ReadinessProbes, ReadinessStage};
+use crate::types::stats_history::{Resolution, StatsPoint};
+use crate::types::supervisor::{CrashEvent, CrashLoopEvent, SupervisorDecision};
+use crate::utils::limits::{apply_resource_limits, release_resource_limits};
+use crate::utils::node_rpc::{
+    check_discv5_bound, check_trin_status, discv5_peer_count, scrape_metrics,
+};
+use crate::utils::ports;
+use crate::utils::probe::spawn_probe;
+use crate::utils::process::{
+    find_orphaned_trin, kill_process, process_start_time, terminate_process,
+};
+use crate::utils::resources::ProcessTreeSampler;
+use crate::utils::storage::dir_size;
 use crate::AppData;
-use log::{info, warn};
-use std::sync::Mutex;
-use std::thread::sleep;
-use std::time::Duration;
-use sysinfo::{Pid, System};
+use log::{error, info, warn};
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
 use tauri::Emitter;
 use tauri::Manager;
 use tauri::State;
-use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_shell::process::{CommandEvent, TerminatedPayload};
 use tauri_plugin_shell::ShellExt;
+use tokio_util::sync::CancellationToken;
+
+// new log records are sent to the log viewer in batches, at most this often
+const LOG_EMIT_INTERVAL: Duration = Duration::from_millis(250);
 
 #[tauri::command]
-pub async fn launch_trin<'l>(
-    app: tauri::AppHandle,
-    app_data: State<'l, Mutex<AppData>>,
-    trin_config: TrinConfig,
-) -> Result<String, String> {
+pub async fn launch_trin(app: tauri::AppHandle, trin_config: TrinConfig) -> Result<String, String> {
+    launch(&app, trin_config).await
+}
+
+// runs when the app starts, without waiting for the window: deals with a trin process
+// that outlived a previous run of the app, then launches trin if the user asked for
+// that. this way the node also starts when the app is launched hidden at login
+pub async fn start_on_app_launch(app: AppHandle) {
+    recover_orphaned_trin(app.clone()).await;
+    let config = match load_app_config(&app) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("unable to load the config: {e}");
+            return;
+        }
+    };
+    if !config.autostart_node {
+        return;
+    }
+    // an orphaned trin process may have been adopted
+    let node_state = app.state::<Mutex<AppData>>().lock().unwrap().node_state;
+    if node_state != NodeState::Stopped {
+        return;
+    }
+    info!("launching trin on app launch");
+    if let Err(e) = launch(&app, config.trin).await {
+        error!("failed to launch trin on app launch: {e}");
+    }
+}
+
+// whether trin has to be restarted to use another config. the config is checked before
+// anything is changed, whatever the node state, so that an invalid config isn't saved
+// as the one to launch trin with, nor leaves the node stopped
+pub async fn needs_relaunch(app: &AppHandle, trin_config: &TrinConfig) -> Result<bool, String> {
+    let errors = validate_trin_config(app, trin_config).await;
+    if !errors.is_empty() {
+        return Err(describe_errors(&errors));
+    }
+    let node_state = app.state::<Mutex<AppData>>().lock().unwrap().node_state;
+    match node_state {
+        NodeState::Stopped => Ok(false),
+        NodeState::Starting | NodeState::Stopping => {
+            Err(format!("unable to restart trin while it is {node_state:?}"))
+        }
+        NodeState::Running | NodeState::Degraded | NodeState::Crashed => Ok(true),
+    }
+}
+
+// restarts trin with another config. if it can't be launched with that config, it is
+// launched with the one it was running with again, rather than being left stopped
+pub async fn relaunch_trin(app: &AppHandle, trin_config: TrinConfig) -> Result<(), String> {
+    let previous = app
+        .state::<Mutex<AppData>>()
+        .lock()
+        .unwrap()
+        .trin_config
+        .clone();
+    info!("restarting trin with a new config");
+    stop_trin(app).await;
+    let Err(e) = launch(app, trin_config).await else {
+        return Ok(());
+    };
+    if let Some(previous) = previous {
+        warn!("unable to restart trin with the new config, using the previous one: {e}");
+        if let Err(e) = launch(app, previous).await {
+            error!("failed to restart trin with the previous config: {e}");
+        }
+    }
+    Err(e)
+}
+
+// a launch requested by the user, or done on their behalf when the app starts
+async fn launch(app: &AppHandle, trin_config: TrinConfig) -> Result<String, String> {
+    // an invalid config is refused before the node state is touched, instead of
+    // waiting for trin to reject it while starting up
+    let errors = validate_trin_config(app, &trin_config).await;
+    if !errors.is_empty() {
+        return Err(describe_errors(&errors));
+    }
+    let app_data = app.state::<Mutex<AppData>>();
+    {
+        let mut state = app_data.lock().unwrap();
+        // refuse to spawn a second sidecar while one is starting or running
+        if !state.node_state.can_transition_to(NodeState::Starting) {
+            return Err(format!(
+                "unable to launch trin while it is {:?}",
+                state.node_state
+            ));
+        }
+        transition(app, &mut state, NodeState::Starting)?;
+        // a launch requested by the user replaces any pending restart,
+        // and starts with a clean crash history
+        if let Some(token) = state.restart_token.take() {
+            token.cancel();
+        }
+        state.supervisor.reset();
+    }
+    let e = match start_trin(app.clone(), trin_config).await {
+        Ok(status) => return Ok(status),
+        Err(e) => e,
+    };
+    let mut state = app_data.lock().unwrap();
+    reset_readiness(app, &mut state);
+    match e {
+        // there's no process that could have crashed, so the error is only returned
+        StartError::NotSpawned(e) => {
+            let _ = transition(app, &mut state, NodeState::Stopped);
+            Err(e)
+        }
+        StartError::Failed(e) => {
+            // trin may have been stopped by the user while starting up, that isn't a crash
+            if transition(app, &mut state, NodeState::Crashed).is_ok() {
+                save_crash_report(app, &state, e.clone());
+            }
+            Err(e)
+        }
+    }
+}
+
+// why trin couldn't be started
+enum StartError {
+    // trin wasn't spawned, eg. because one of its ports is taken
+    NotSpawned(String),
+    // trin was spawned, but exited or didn't respond while starting up
+    Failed(String),
+}
+
+// spawns the trin sidecar and the tasks that monitor it, this is used both
+// for launches requested by the user and for restarts done by the supervisor
+async fn start_trin(app: AppHandle, trin_config: TrinConfig) -> Result<String, StartError> {
     info!("starting trin with config: {:?}", trin_config);
 
-    let web3_http_address = format!("http://127.0.0.1:{}", trin_config.httpPort);
+    // fail early with a useful error, instead of letting trin crash on a bad
+    // config or a taken port
+    let trin_args = trin_config.trin_args().map_err(StartError::NotSpawned)?;
+    let conflicts = port_conflicts(&trin_config)
+        .await
+        .map_err(StartError::NotSpawned)?;
+    if !conflicts.is_empty() {
+        let conflicts: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+        return Err(StartError::NotSpawned(conflicts.join("; ")));
+    }
 
     let (mut rx, child) = app
         .shell()
         .sidecar("trin")
         .expect("failed to create `trin` binary command")
-        .args([
-            "--web3-transport=http",
-            "--portal-subnetworks=history,state,beacon",
-            format!("--trusted-block-root={}", trin_config.trustedBlockRoot).as_str(),
-            format!("--web3-http-address={}", web3_http_address).as_str(),
-            format!("--mb={}", trin_config.storage).as_str(),
-        ])
+        .args(trin_args.args)
+        .envs(trin_args.envs)
         .spawn()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| StartError::NotSpawned(e.to_string()))?;
+    let pid = child.pid();
+    save_pid_file(&app, pid, &trin_config);
+    let applied_limits = apply_resource_limits(pid, &trin_config);
+    for error in &applied_limits.errors {
+        warn!("{error}");
+    }
+    app.emit("trin-limits", applied_limits.clone())
+        .expect("failed to emit event");
+    {
+        let state = app.state::<Mutex<AppData>>();
+        let mut state = state.lock().unwrap();
+        state.applied_limits = Some(applied_limits);
+        // reborrow the guard, so that its fields can be borrowed separately
+        let state = &mut *state;
+        state.activity.process_started(&mut state.node_stats);
+        state.storage.reset();
+        state.trin_output.clear();
+        state.trin_exit = None;
+        state.expected_exit = None;
+        state.readiness_probes = ReadinessProbes {
+            process_running: true,
+            beacon_synced: !trin_config.subnetworks.contains(&Subnetwork::Beacon),
+            ..Default::default()
+        };
+        update_readiness(&app, state);
+    }
 
     // spawn a thread that will read the stdout of the trin process
     let app_clone = app.clone();
-    let log_handle = tauri::async_runtime::spawn(async move {
+    let log_token = CancellationToken::new();
+    let token = log_token.clone();
+    let log_parsers = LogParsers::default();
+    tauri::async_runtime::spawn(async move {
+        let mut log_batch = Vec::new();
+        let mut emit_interval = tokio::time::interval(LOG_EMIT_INTERVAL);
         // read events such as stdout
-        while let Some(event) = rx.recv().await {
-            if let CommandEvent::Stdout(line_bytes) = event {
-                let line = String::from_utf8_lossy(&line_bytes);
-                info!("Child process stdout: {}", line);
-                if line.contains("trin_history: reports~ data:") {
-                    let log = SubnetworkDataLog::parse_log_line(&line);
-                    match log {
-                        Ok(log) => {
-                            let state = app_clone.state::<Mutex<AppData>>();
-                            let mut state = state.lock().unwrap();
-                            state.node_stats.history_data = log;
-                        }
-                        Err(e) => {
-                            warn!("Failed to parse log line: {e}");
-                        }
-                    }
-                } else if line.contains("trin_state: reports~ data:") {
-                    let log = SubnetworkDataLog::parse_log_line(&line);
-                    match log {
-                        Ok(log) => {
+        loop {
+            let event = tokio::select! {
+                _ = token.cancelled() => break,
+                _ = emit_interval.tick() => {
+                    emit_logs(&app_clone, &mut log_batch);
+                    continue;
+                }
+                event = rx.recv() => event,
+            };
+            let Some(event) = event else {
+                break;
+            };
+            match event {
+                CommandEvent::Stdout(line_bytes) => {
+                    let line = String::from_utf8_lossy(&line_bytes);
+                    let Some(log_line) =
+                        record_output(&app_clone, LogStream::Stdout, &line, &mut log_batch)
+                    else {
+                        continue;
+                    };
+                    match log_parsers.parse(&log_line) {
+                        Some(Ok(update)) => {
                             let state = app_clone.state::<Mutex<AppData>>();
                             let mut state = state.lock().unwrap();
-                            state.node_stats.state_data = log;
+                            // the logs are only a fallback for when metrics are unavailable
+                            if state.node_stats.stats_source == StatsSource::Logs {
+                                update.apply(&mut state.node_stats);
+                            }
                         }
-                        Err(e) => {
+                        Some(Err(e)) => {
                             warn!("Failed to parse log line: {e}");
-                        }
-                    }
-                } else if line.contains("trin_beacon: reports~ data:") {
-                    let log = SubnetworkDataLog::parse_log_line(&line);
-                    match log {
-                        Ok(log) => {
                             let state = app_clone.state::<Mutex<AppData>>();
-                            let mut state = state.lock().unwrap();
-                            state.node_stats.beacon_data = log;
-                        }
-                        Err(e) => {
-                            warn!("Failed to parse log line: {e}");
+                            state.lock().unwrap().node_stats.log_parse_failures += 1;
                         }
+                        None => {}
                     }
                 }
+                CommandEvent::Stderr(line_bytes) => {
+                    let line = String::from_utf8_lossy(&line_bytes);
+                    record_output(&app_clone, LogStream::Stderr, &line, &mut log_batch);
+                }
+                CommandEvent::Error(e) => {
+                    error!("Child process error: {e}");
+                }
+                CommandEvent::Terminated(exit) => {
+                    handle_trin_exit(&app_clone, pid, exit);
+                }
+                _ => {}
             }
         }
+        emit_logs(&app_clone, &mut log_batch);
     });
 
     // if trin is not responding to jsonrpc requests after 30 seconds,
@@ -94,119 +290,879 @@ pub async fn launch_trin<'l>(
         info!("checking trin");
         // trin has successfully started
         if check_trin_status(&trin_config.httpPort).await {
+            let state = app.state::<Mutex<AppData>>();
+            let mut state = state.lock().unwrap();
+            state.readiness_probes.rpc_bound = true;
+            update_readiness(&app, &mut state);
             break;
         }
-        sleep(Duration::from_secs(1));
+        let state = app.state::<Mutex<AppData>>();
+        let (trin_exit, node_state) = {
+            let state = state.lock().unwrap();
+            (state.trin_exit.clone(), state.node_state)
+        };
+        // no point in waiting for the rpc server if trin has already exited
+        if let Some(exit) = trin_exit {
+            return Err(StartError::Failed(format!(
+                "{} while starting up",
+                describe_exit(&exit)
+            )));
+        }
+        // trin was stopped by the user while starting up
+        if node_state != NodeState::Starting {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
         i += 1;
         if i == 20 {
+            state.lock().unwrap().expected_exit = Some(pid);
             let _ = child.kill();
-            return Err("unable to get a response from the rpc server".to_string());
+            let message = "unable to get a response from the rpc server".to_string();
+            return Err(StartError::Failed(message));
         }
     }
 
-    // spawn a thread that will ping the trin node every 3 seconds
-    // to make sure it is still running
+    let app_data = app.state::<Mutex<AppData>>();
+    let mut app_data = app_data.lock().unwrap();
+    if transition(&app, &mut app_data, NodeState::Running).is_err() {
+        app_data.expected_exit = Some(pid);
+        let _ = child.kill();
+        log_token.cancel();
+        let message = "trin was stopped while starting up".to_string();
+        return Err(StartError::Failed(message));
+    }
+
+    info!("checking trin status, pid: {:?}", pid);
+    let status_token = CancellationToken::new();
+    spawn_status_probes(&app, pid, &trin_config, status_token.clone());
+
+    // todo: test by killing this - then remove
+    info!("Child process started: {:?}", pid);
+    app_data.trin_config = Some(trin_config);
+    app_data.status_token = Some(status_token);
+    app_data.log_token = Some(log_token);
+    app_data.trin_handle = Some(child);
+    Ok("started".to_string())
+}
+
+// spawns the tasks that keep an eye on the running trin node. each probe runs on
+// its own cadence, so eg. a slow beacon request doesn't hold up the cpu stats
+fn spawn_status_probes(
+    app: &AppHandle,
+    pid: u32,
+    trin_config: &TrinConfig,
+    token: CancellationToken,
+) {
+    let http_port = trin_config.httpPort;
+    let metrics_port = trin_config.metricsPort;
+    let discovery_port = trin_config.discoveryPort;
+
+    // ping the trin node every 3 seconds to make sure it is still running
     let app_clone = app.clone();
-    let pid = child.pid();
-    let status_handle = tauri::async_runtime::spawn(async move {
-        info!("checking trin status, pid: {:?}", pid);
-        // Initialize system information gatherer
-        let mut sys = System::new_all();
-        loop {
-            // Refresh process list and CPU usage
-            sys.refresh_all();
-
-            // Get process ID for the port
-            let pid = Pid::from(pid as usize);
-
-            // Get the main process and all its children
-            let mut total_cpu = 0.0;
-            let mut process_count = 0;
-
-            // Check main process
-            if let Some(process) = sys.process(pid) {
-                total_cpu += process.cpu_usage();
-                process_count += 1;
-
-                // Get all processes to check for children
-                for (_pid_check, process_check) in sys.processes() {
-                    if process_check.parent() == Some(pid) {
-                        total_cpu += process_check.cpu_usage();
-                        process_count += 1;
+    spawn_probe(token.clone(), Duration::from_secs(3), move || {
+        let app = app_clone.clone();
+        async move {
+            if check_trin_status(&http_port).await {
+                return ControlFlow::Continue(());
+            }
+            let reason = "trin stopped responding to jsonrpc requests".to_string();
+            handle_trin_crash(&app, pid, reason);
+            ControlFlow::Break(())
+        }
+    });
+
+    // read the subnetwork & network stats from trin's metrics, falling back to the
+    // log scraper while they're unavailable (eg. for older trin binaries)
+    let app_clone = app.clone();
+    spawn_probe(token.clone(), Duration::from_secs(3), move || {
+        let app = app_clone.clone();
+        async move {
+            let metrics = scrape_metrics(metrics_port).await;
+            let state = app.state::<Mutex<AppData>>();
+            let mut state = state.lock().unwrap();
+            match metrics {
+                Ok(metrics) => metrics.apply(&mut state.node_stats),
+                Err(e) => {
+                    if state.node_stats.stats_source == StatsSource::Metrics {
+                        warn!("failed to scrape trin metrics, falling back to logs: {e}");
                     }
+                    state.node_stats.stats_source = StatsSource::Logs;
                 }
             }
+            ControlFlow::Continue(())
+        }
+    });
 
-            // these requests can take some time to return, and we don't want to block
-            // this loop, so they're probably worth moving to a separate thread soon
-            let optimistic_update = portal_beaconOptimisticUpdate(trin_config.httpPort).await;
-            let finality_update = portal_beaconFinalityUpdate(trin_config.httpPort).await;
-
-            // idk why but this has to happen before updating the trin stats
-            if !check_trin_status(&trin_config.httpPort).await {
-                app_clone
-                    .emit("trin-crashed", ())
-                    .expect("failed to emit event");
-                break;
+    // update the resource usage and send the latest stats to the frontend
+    let app_clone = app.clone();
+    let sampler = Arc::new(Mutex::new(ProcessTreeSampler::new(pid)));
+    spawn_probe(token.clone(), Duration::from_secs(3), move || {
+        let app = app_clone.clone();
+        let sampler = sampler.clone();
+        async move {
+            // sysinfo refreshes the processes synchronously, which blocks for a while
+            let sample =
+                tauri::async_runtime::spawn_blocking(move || sampler.lock().unwrap().sample())
+                    .await;
+            let Ok(sample) = sample else {
+                return ControlFlow::Continue(());
+            };
+            // only the new values are worked out while holding the app state, saving
+            // them and notifying the user happens once it has been released
+            let (stats, activity, alerts, alert_history) = {
+                let state = app.state::<Mutex<AppData>>();
+                let mut state = state.lock().unwrap();
+                let stats = &mut state.node_stats;
+                stats.cpu = sample.cpu;
+                stats.pid = pid as usize;
+                stats.memory = sample.memory;
+                stats.virtual_memory = sample.virtual_memory;
+                stats.threads = sample.threads;
+                stats.open_fds = sample.open_fds;
+                stats.disk_read_rate = sample.disk_read_rate;
+                stats.disk_write_rate = sample.disk_write_rate;
+                let timestamp = unix_timestamp();
+                // reborrow the guard, so that its fields can be borrowed separately
+                let state = &mut *state;
+                let storage_mb = state
+                    .trin_config
+                    .as_ref()
+                    .map(|config| config.storage)
+                    .unwrap_or_default();
+                let activity = state.activity.update(timestamp, &mut state.node_stats);
+                state.storage.update(&mut state.node_stats, storage_mb);
+                state.stats_history.record(timestamp, &state.node_stats);
+                let alerts = state
+                    .alerts
+                    .evaluate(timestamp, &state.node_stats, storage_mb as f64);
+                let alert_history = match alerts.is_empty() {
+                    true => None,
+                    false => state.alerts.history_snapshot(),
+                };
+                (state.node_stats, activity, alerts, alert_history)
+            };
+            if let Some(activity) = activity {
+                save_activity(activity).await;
             }
+            if let Some(alert_history) = alert_history {
+                match tauri::async_runtime::spawn_blocking(move || alert_history.save()).await {
+                    Ok(Err(e)) => warn!("unable to save alert history: {e}"),
+                    Err(e) => warn!("unable to save alert history: {e}"),
+                    Ok(Ok(())) => {}
+                }
+            }
+            for alert in alerts {
+                notify_alert(&app, alert);
+            }
+            app.emit("trin-stats", stats).expect("failed to emit event");
+            ControlFlow::Continue(())
+        }
+    });
 
-            // update cpu & latest finalized header in trin stats
-            {
-                let state = app_clone.state::<Mutex<AppData>>();
+    // the beacon updates change once per slot, so there's no point in asking more often.
+    // these requests can take up to 10 seconds to return. trin doesn't serve them at all
+    // without the beacon subnetwork
+    if trin_config.subnetworks.contains(&Subnetwork::Beacon) {
+        let app_clone = app.clone();
+        spawn_probe(token.clone(), Duration::from_secs(12), move || {
+            let app = app_clone.clone();
+            async move {
+                let finality_update = portal_beaconFinalityUpdate(http_port).await;
+                let state = app.state::<Mutex<AppData>>();
                 let mut state = state.lock().unwrap();
-                state.node_stats.cpu = total_cpu as f32;
-                state.node_stats.pid = pid.into();
+                state.readiness_probes.beacon_synced = finality_update.is_ok();
                 if let Ok(update) = finality_update {
-                    state.node_stats.latest_finalized_block = update
-                        .finalized_header_deneb()
-                        .unwrap()
-                        .execution
-                        .block_number;
+                    // only deneb updates are understood, other forks are logged & skipped
+                    match update.finalized_header_deneb() {
+                        Ok(header) => {
+                            state.node_stats.latest_finalized_block = header.execution.block_number
+                        }
+                        Err(e) => warn!("unexpected finality update fork: {e:?}"),
+                    }
                 }
+                update_readiness(&app, &mut state);
+                ControlFlow::Continue(())
+            }
+        });
+
+        let app_clone = app.clone();
+        spawn_probe(token.clone(), Duration::from_secs(12), move || {
+            let app = app_clone.clone();
+            async move {
+                let optimistic_update = portal_beaconOptimisticUpdate(http_port).await;
                 if let Ok(update) = optimistic_update {
-                    state.node_stats.latest_optimistic_block = update
-                        .attested_header_deneb()
-                        .unwrap()
-                        .execution
-                        .block_number;
-                }
-                app_clone
-                    .emit("trin-stats", state.node_stats.clone())
-                    .expect("failed to emit event");
-                // use braces to drop the lock asap
+                    let state = app.state::<Mutex<AppData>>();
+                    let mut state = state.lock().unwrap();
+                    match update.attested_header_deneb() {
+                        Ok(header) => {
+                            state.node_stats.latest_optimistic_block = header.execution.block_number
+                        }
+                        Err(e) => warn!("unexpected optimistic update fork: {e:?}"),
+                    }
+                }
+                ControlFlow::Continue(())
             }
+        });
+    }
 
-            sleep(Duration::from_secs(3));
+    // measure the size of trin's data dir, walking it is too slow to do on every stats update
+    let data_dir = trin_data_dir(app, trin_config);
+    let app_clone = app.clone();
+    spawn_probe(token.clone(), Duration::from_secs(30), move || {
+        let app = app_clone.clone();
+        let data_dir = data_dir.clone();
+        async move {
+            let Some(data_dir) = data_dir else {
+                warn!("unable to find trin's data dir, its size won't be measured");
+                return ControlFlow::Break(());
+            };
+            let size = tauri::async_runtime::spawn_blocking(move || dir_size(&data_dir))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|size| size.map_err(|e| e.to_string()));
+            match size {
+                Ok(size) => {
+                    let state = app.state::<Mutex<AppData>>();
+                    state.lock().unwrap().storage.record(unix_timestamp(), size);
+                }
+                Err(e) => warn!("failed to measure trin's data dir: {e}"),
+            }
+            ControlFlow::Continue(())
         }
     });
 
-    // todo: test by killing this - then remove
-    info!("Child process started: {:?}", pid);
-    let mut app_data = app_data.lock().unwrap();
-    app_data.status_handle = Some(status_handle);
-    app_data.log_handle = Some(log_handle);
-    app_data.trin_handle = Some(child);
-    Ok("started".to_string())
+    // the rpc server responding doesn't mean that trin is participating
+    // in the network, so check how far along it actually is
+    let app_clone = app.clone();
+    spawn_probe(token, Duration::from_secs(10), move || {
+        let app = app_clone.clone();
+        async move {
+            let (discv5_bound, peers) = tokio::join!(
+                check_discv5_bound(discovery_port),
+                discv5_peer_count(&http_port)
+            );
+            let state = app.state::<Mutex<AppData>>();
+            let mut state = state.lock().unwrap();
+            state.readiness_probes.discv5_bound = discv5_bound;
+            state.readiness_probes.peers = peers.unwrap_or_default();
+            update_readiness(&app, &mut state);
+            // trin isn't participating in the network if discv5 isn't bound
+            let next = if discv5_bound {
+                NodeState::Running
+            } else {
+                NodeState::Degraded
+            };
+            if matches!(state.node_state, NodeState::Running | NodeState::Degraded) {
+                let _ = transition(&app, &mut state, next);
+            }
+            ControlFlow::Continue(())
+        }
+    });
+}
+
+// shows a native notification for the alert, and adds it to the in-app alert history
+fn notify_alert(app: &AppHandle, alert: Alert) {
+    let title = match alert.resolved {
+        true => format!("Resolved: {}", alert.rule_name),
+        false => alert.rule_name.clone(),
+    };
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(&alert.message)
+        .show()
+    {
+        warn!("unable to show notification: {e}");
+    }
+    app.emit("trin-alert", alert).expect("failed to emit event");
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// keeps a line of trin's output for crash reports & the log viewer, and forwards it to
+// the app's own log. the record is added to `batch`, to be emitted with `emit_logs`.
+// returns the parsed log line, if it's a tracing record
+fn record_output(
+    app: &AppHandle,
+    stream: LogStream,
+    line: &str,
+    batch: &mut Vec<LogRecord>,
+) -> Option<LogLine> {
+    let log_line = LogLine::parse(line);
+    let record = {
+        let state = app.state::<Mutex<AppData>>();
+        let mut state = state.lock().unwrap();
+        state.trin_output.push(line);
+        state.trin_logs.push(stream, line, log_line.as_ref())
+    };
+    match (record.level, &record.target) {
+        (Some(level), Some(target)) => {
+            log::log!(target: "trin", level.into(), "{target}: {}", record.message)
+        }
+        _ if stream == LogStream::Stderr => warn!(target: "trin", "{}", record.message),
+        _ => info!(target: "trin", "{}", record.message),
+    }
+    batch.push(record);
+    log_line
+}
+
+// sends the records that were added since the last call to the log viewer, trin can log
+// hundreds of lines a second and an event per line would flood the frontend
+fn emit_logs(app: &AppHandle, batch: &mut Vec<LogRecord>) {
+    if !batch.is_empty() {
+        app.emit("trin-log", std::mem::take(batch))
+            .expect("failed to emit event");
+    }
+}
+
+// called by the log task once the trin process has exited
+fn handle_trin_exit(app: &AppHandle, pid: u32, exit: TerminatedPayload) {
+    let reason = describe_exit(&exit);
+    let expected = {
+        let state = app.state::<Mutex<AppData>>();
+        let mut state = state.lock().unwrap();
+        state.trin_exit = Some(exit);
+        state.expected_exit == Some(pid)
+    };
+    remove_pid_file(app, pid);
+    if expected {
+        info!("{reason}");
+    } else {
+        handle_trin_crash(app, pid, reason);
+    }
+}
+
+// called once trin is found to be dead, or no longer responding. records a crash report,
+// then either restarts trin using the last config it was launched with, or gives up if
+// trin is stuck in a crash loop
+fn handle_trin_crash(app: &AppHandle, pid: u32, reason: String) {
+    warn!("trin crashed: {reason}");
+    let state = app.state::<Mutex<AppData>>();
+    let mut state = state.lock().unwrap();
+    // if trin crashed while starting up, `start_trin` returns the error instead
+    if trin_pid(&state) != Some(pid) {
+        return;
+    }
+    let report = save_crash_report(app, &state, reason);
+    // make sure the dead (or hanging) process is gone before starting a new one
+    state.expected_exit = Some(pid);
+    if let Some(child) = state.trin_handle.take() {
+        let _ = child.kill();
+    }
+    if state.adopted_pid.take().is_some() {
+        kill_process(pid);
+    }
+    remove_pid_file(app, pid);
+    if let Some(token) = state.status_token.take() {
+        token.cancel();
+    }
+    reset_readiness(app, &mut state);
+    let _ = transition(app, &mut state, NodeState::Crashed);
+    schedule_restart(app, &mut state, report);
+}
+
+fn schedule_restart(app: &AppHandle, state: &mut AppData, report: CrashReport) {
+    // the alerts of the dead process don't carry over to the next one
+    state.alerts.reset();
+    let Some(trin_config) = state.trin_config.clone() else {
+        warn!("no trin config available, unable to restart trin");
+        return;
+    };
+
+    match state.supervisor.record_crash(Instant::now()) {
+        SupervisorDecision::Restart(delay) => {
+            info!("restarting trin in {} seconds", delay.as_secs());
+            let event = CrashEvent {
+                reason: report.reason.clone(),
+                restart_in_secs: delay.as_secs(),
+                recent_crashes: state.supervisor.recent_crashes(),
+                report,
+            };
+            app.emit("trin-crashed", event)
+                .expect("failed to emit event");
+            let app_clone = app.clone();
+            let restart_token = CancellationToken::new();
+            let token = restart_token.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::select! {
+                    _ = token.cancelled() => return,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                {
+                    let state = app_clone.state::<Mutex<AppData>>();
+                    let mut state = state.lock().unwrap();
+                    if transition(&app_clone, &mut state, NodeState::Starting).is_err() {
+                        return;
+                    }
+                }
+                match start_trin(app_clone.clone(), trin_config).await {
+                    Ok(_) => {
+                        let state = app_clone.state::<Mutex<AppData>>();
+                        state.lock().unwrap().supervisor.record_restart();
+                        app_clone
+                            .emit("trin-restarted", ())
+                            .expect("failed to emit event");
+                    }
+                    Err(e) => {
+                        let state = app_clone.state::<Mutex<AppData>>();
+                        let mut state = state.lock().unwrap();
+                        reset_readiness(&app_clone, &mut state);
+                        // trin was stopped by the user while it was being restarted
+                        if transition(&app_clone, &mut state, NodeState::Crashed).is_err() {
+                            return;
+                        }
+                        let report = match e {
+                            // the crash report of the last process that ran is kept
+                            StartError::NotSpawned(e) => {
+                                let reason = format!("failed to restart trin: {e}");
+                                CrashReport::new(reason, None, &state.trin_output)
+                            }
+                            StartError::Failed(e) => {
+                                let reason = format!("failed to restart trin: {e}");
+                                save_crash_report(&app_clone, &state, reason)
+                            }
+                        };
+                        schedule_restart(&app_clone, &mut state, report);
+                    }
+                }
+            });
+            state.restart_token = Some(restart_token);
+        }
+        SupervisorDecision::CrashLoop(loop_reason) => {
+            error!("{loop_reason}, no longer restarting trin");
+            let event = CrashLoopEvent {
+                reason: loop_reason,
+                recent_crashes: state.supervisor.recent_crashes(),
+                report,
+            };
+            app.emit("trin-crash-loop", event)
+                .expect("failed to emit event");
+        }
+    }
+}
+
+// writes a crash report to the app data dir, so it can be inspected later
+fn save_crash_report(app: &AppHandle, state: &AppData, reason: String) -> CrashReport {
+    let report = CrashReport::new(reason, state.trin_exit.as_ref(), &state.trin_output);
+    let saved = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| report.save(&dir));
+    if let Err(e) = saved {
+        warn!("failed to save crash report: {e}");
+    }
+    report
+}
+
+// recomputes the readiness stage from the latest probe results,
+// and lets the frontend know if it changed
+fn update_readiness(app: &AppHandle, state: &mut AppData) {
+    let stage = ReadinessStage::from_probes(&state.readiness_probes);
+    if state.readiness == stage {
+        return;
+    }
+    info!("trin readiness: {:?} -> {:?}", state.readiness, stage);
+    let event = ReadinessEvent {
+        stage,
+        previous: state.readiness,
+    };
+    state.readiness = stage;
+    app.emit("trin-readiness", event)
+        .expect("failed to emit event");
+}
+
+fn reset_readiness(app: &AppHandle, state: &mut AppData) {
+    state.readiness_probes = ReadinessProbes::default();
+    update_readiness(app, state);
+}
+
+// moves the node to its next lifecycle state, refusing invalid transitions
+fn transition(app: &AppHandle, state: &mut AppData, next: NodeState) -> Result<(), String> {
+    let previous = state.node_state;
+    if previous == next {
+        return Ok(());
+    }
+    if !previous.can_transition_to(next) {
+        return Err(format!(
+            "invalid node state transition: {previous:?} -> {next:?}"
+        ));
+    }
+    info!("trin node state: {previous:?} -> {next:?}");
+    state.node_state = next;
+    // the uptime of the node is reported by the metrics exporter, it isn't reset
+    // while the node flaps between running & degraded
+    if next.is_up() && !previous.is_up() {
+        state.running_since = Some(Instant::now());
+    } else if matches!(next, NodeState::Stopped | NodeState::Crashed) {
+        state.running_since = None;
+    }
+    let event = NodeStateEvent {
+        state: next,
+        previous,
+    };
+    app.emit("trin-node-state", event)
+        .expect("failed to emit event");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_node_status<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+) -> Result<NodeStatus, String> {
+    let app_data = app_data.lock().unwrap();
+    Ok(NodeStatus {
+        state: app_data.node_state,
+        readiness: app_data.readiness,
+        pid: trin_pid(&app_data),
+    })
+}
+
+#[tauri::command]
+pub async fn get_node_readiness<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+) -> Result<ReadinessStage, String> {
+    Ok(app_data.lock().unwrap().readiness)
+}
+
+#[tauri::command]
+pub async fn check_trin_ports(trin_config: TrinConfig) -> Result<Vec<PortConflict>, String> {
+    port_conflicts(&trin_config).await
+}
+
+async fn port_conflicts(trin_config: &TrinConfig) -> Result<Vec<PortConflict>, String> {
+    let http_port = u16::try_from(trin_config.httpPort)
+        .map_err(|_| format!("invalid http port: {}", trin_config.httpPort))?;
+    let (metrics_port, discovery_port) = (trin_config.metricsPort, trin_config.discoveryPort);
+    // finding the process that holds a port walks the open files of every process
+    tauri::async_runtime::spawn_blocking(move || {
+        ports::check_trin_ports(http_port, metrics_port, discovery_port)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_applied_limits<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+) -> Result<Option<AppliedLimits>, String> {
+    Ok(app_data.lock().unwrap().applied_limits.clone())
+}
+
+// writes the stats history to disk every minute, the stats probe only records it in memory
+// so that it doesn't do any io while holding the app state
+pub async fn save_stats_history(app: AppHandle) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        let writes = {
+            let state = app.state::<Mutex<AppData>>();
+            let mut state = state.lock().unwrap();
+            state.stats_history.take_writes()
+        };
+        match tauri::async_runtime::spawn_blocking(move || writes.apply()).await {
+            Ok(Err(e)) => warn!("unable to save stats history: {e}"),
+            Err(e) => warn!("unable to save stats history: {e}"),
+            Ok(Ok(())) => {}
+        }
+    }
+}
+
+// removes the cgroup trin was moved into, on a blocking thread since it may have to
+// wait for trin to leave it
+async fn release_limits(applied_limits: AppliedLimits) {
+    let released =
+        tauri::async_runtime::spawn_blocking(move || release_resource_limits(&applied_limits));
+    match released.await {
+        Ok(Err(e)) => warn!("unable to release resource limits: {e}"),
+        Err(e) => warn!("unable to release resource limits: {e}"),
+        Ok(Ok(())) => {}
+    }
+}
+
+// writes the activity totals on a blocking thread, they are only snapshotted while
+// holding the app state
+async fn save_activity(activity: ActivitySnapshot) {
+    match tauri::async_runtime::spawn_blocking(move || activity.save()).await {
+        Ok(Err(e)) => warn!("unable to save activity totals: {e}"),
+        Err(e) => warn!("unable to save activity totals: {e}"),
+        Ok(Ok(())) => {}
+    }
+}
+
+// the history of a single metric (eg. "historyData.radius") between two unix timestamps.
+// without a resolution, the finest one that covers the whole range is used
+#[tauri::command]
+pub async fn get_stats_history<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+    metric: String,
+    from: u64,
+    to: u64,
+    resolution: Option<Resolution>,
+) -> Result<Vec<StatsPoint>, String> {
+    let app_data = app_data.lock().unwrap();
+    app_data
+        .stats_history
+        .query(&metric, from, to, resolution, unix_timestamp())
+}
+
+// pages back through trin's recent logs, newest first, see `LogQuery` for the filters
+#[tauri::command]
+pub async fn get_trin_logs<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+    query: LogQuery,
+) -> Result<LogPage, String> {
+    app_data.lock().unwrap().trin_logs.query(&query)
+}
+
+#[tauri::command]
+pub async fn get_trin_log_targets<'l>(
+    app_data: State<'l, Mutex<AppData>>,
+) -> Result<Vec<String>, String> {
+    Ok(app_data.lock().unwrap().trin_logs.targets())
+}
+
+#[tauri::command]
+pub async fn clear_trin_logs<'l>(app_data: State<'l, Mutex<AppData>>) -> Result<(), String> {
+    app_data.lock().unwrap().trin_logs.clear();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_last_crash_report(app: AppHandle) -> Result<Option<CrashReport>, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    CrashReport::load(&dir)
 }
 
 #[tauri::command]
-pub async fn shutdown_trin<'l>(app_data: State<'l, Mutex<AppData>>) -> Result<String, String> {
+pub async fn shutdown_trin(app: AppHandle) -> Result<String, String> {
+    stop_trin(&app).await;
+    Ok(format!("stopped trin"))
+}
+
+// gracefully stops trin: SIGTERM first, so it gets a chance to flush its db,
+// and only kill it if it hasn't exited after the configured grace period
+pub async fn stop_trin(app: &AppHandle) {
     info!("stopping trin");
-    let mut app_data = app_data.lock().unwrap();
-    if let Some(child) = app_data.trin_handle.take() {
-        child.kill().expect("failed to kill child process");
+    let (activity, applied_limits, child, adopted_pid, log_token, grace_period) = {
+        let app_data = app.state::<Mutex<AppData>>();
+        let mut app_data = app_data.lock().unwrap();
+        // cancel any pending restart, so the supervisor doesn't bring trin back up
+        if let Some(token) = app_data.restart_token.take() {
+            token.cancel();
+        }
+        match app_data.node_state {
+            NodeState::Stopped | NodeState::Stopping => {
+                warn!("trin is already {:?}", app_data.node_state);
+                return;
+            }
+            // there's no process to stop, only the pending restart
+            NodeState::Crashed => {
+                let _ = transition(app, &mut app_data, NodeState::Stopped);
+                if let Some(applied_limits) = app_data.applied_limits.take() {
+                    tauri::async_runtime::spawn(release_limits(applied_limits));
+                }
+                return;
+            }
+            _ => {
+                let _ = transition(app, &mut app_data, NodeState::Stopping);
+            }
+        }
+        // stop monitoring first, otherwise the shutdown is reported as a crash
+        if let Some(token) = app_data.status_token.take() {
+            token.cancel();
+        } else {
+            warn!("unable to stop status probes");
+        }
+        reset_readiness(app, &mut app_data);
+        let grace_period = app_data
+            .trin_config
+            .as_ref()
+            .map(|config| config.shutdownTimeout)
+            .unwrap_or_default();
+        app_data.expected_exit = trin_pid(&app_data);
+        app_data.alerts.reset();
+        (
+            app_data.activity.snapshot(),
+            app_data.applied_limits.take(),
+            app_data.trin_handle.take(),
+            app_data.adopted_pid.take(),
+            app_data.log_token.take(),
+            Duration::from_secs(grace_period),
+        )
+        // use braces to drop the lock before waiting on trin
+    };
+    if let Some(activity) = activity {
+        save_activity(activity).await;
+    }
+
+    if let Some(pid) = child.as_ref().map(|child| child.pid()).or(adopted_pid) {
+        if terminate_process(pid, grace_period).await {
+            info!("trin exited gracefully");
+        } else {
+            warn!(
+                "trin did not exit within {} seconds, killing it",
+                grace_period.as_secs()
+            );
+            match child {
+                Some(child) => {
+                    // trin may have exited on its own in the meantime
+                    if let Err(e) = child.kill() {
+                        warn!("failed to kill trin: {e}");
+                    }
+                }
+                None => {
+                    kill_process(pid);
+                }
+            }
+        }
+        remove_pid_file(app, pid);
     } else {
         warn!("unable to kill trin child process");
     }
-    if let Some(handle) = app_data.log_handle.take() {
-        handle.abort();
-    } else {
-        warn!("unable to kill log handle");
+    if let Some(applied_limits) = applied_limits {
+        release_limits(applied_limits).await;
     }
-    if let Some(handle) = app_data.status_handle.take() {
-        handle.abort();
+    // the log task is stopped last, so it still records trin's output while it shuts down
+    if let Some(token) = log_token {
+        token.cancel();
     } else {
-        warn!("unable to kill status handle");
+        warn!("unable to stop log task");
     }
-    Ok(format!("stopped trin"))
+    let app_data = app.state::<Mutex<AppData>>();
+    let _ = transition(app, &mut app_data.lock().unwrap(), NodeState::Stopped);
+}
+
+// stops trin, or waits for a stop that is already in flight to finish, eg. when the app
+// is quit while the user is stopping trin
+pub async fn stop_trin_and_wait(app: &AppHandle) {
+    stop_trin(app).await;
+    let state = app.state::<Mutex<AppData>>();
+    while state.lock().unwrap().node_state == NodeState::Stopping {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+// the pid of the trin process we're managing, whether we spawned it or adopted it
+fn trin_pid(state: &AppData) -> Option<u32> {
+    state
+        .trin_handle
+        .as_ref()
+        .map(|child| child.pid())
+        .or(state.adopted_pid)
+}
+
+fn save_pid_file(app: &AppHandle, pid: u32, trin_config: &TrinConfig) {
+    let Some(start_time) = process_start_time(pid) else {
+        warn!("unable to find trin process {pid}, not writing a pid file");
+        return;
+    };
+    let pid_file = PidFile {
+        pid,
+        start_time,
+        trin_config: trin_config.clone(),
+    };
+    let saved = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| pid_file.save(&dir));
+    if let Err(e) = saved {
+        warn!("failed to save pid file: {e}");
+    }
+}
+
+fn remove_pid_file(app: &AppHandle, pid: u32) {
+    let removed = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| PidFile::remove(&dir, pid));
+    if let Err(e) = removed {
+        warn!("failed to remove pid file: {e}");
+    }
+}
+
+// if the app was killed without shutting trin down, the sidecar
+// keeps running and holds on to the http port & db lock, so we either adopt it or
+// shut it down, depending on the user's preference
+async fn recover_orphaned_trin(app: AppHandle) {
+    let pid_file = match app.path().app_data_dir() {
+        Ok(dir) => PidFile::load(&dir).unwrap_or_else(|e| {
+            warn!("failed to read pid file: {e}");
+            None
+        }),
+        Err(e) => {
+            warn!("unable to find app data dir: {e}");
+            None
+        }
+    };
+    let Some(pid) = find_orphaned_trin(pid_file.as_ref()) else {
+        return;
+    };
+    let policy = load_app_config(&app)
+        .map(|config| config.orphaned_node_policy)
+        .unwrap_or_else(|e| {
+            warn!("unable to load the config: {e}");
+            OrphanPolicy::default()
+        });
+    info!("found orphaned trin process {pid}, policy: {policy:?}");
+
+    // we can only adopt trin if we know which config (and rpc port) it was launched with
+    let pid_file = pid_file.filter(|pid_file| pid_file.pid == pid);
+    if let (OrphanPolicy::Adopt, Some(pid_file)) = (policy, &pid_file) {
+        if check_trin_status(&pid_file.trin_config.httpPort).await {
+            adopt_trin(&app, pid_file.clone());
+            return;
+        }
+        warn!("orphaned trin process {pid} isn't responding, shutting it down");
+    }
+
+    let grace_period = pid_file
+        .map(|pid_file| pid_file.trin_config.shutdownTimeout)
+        .unwrap_or(30);
+    if !terminate_process(pid, Duration::from_secs(grace_period)).await {
+        warn!("orphaned trin process {pid} did not exit, killing it");
+        kill_process(pid);
+    }
+    remove_pid_file(&app, pid);
+}
+
+// reattaches monitoring to a trin process that we didn't spawn. its stdout isn't
+// available to us, so only the stats that come from rpc requests are updated
+fn adopt_trin(app: &AppHandle, pid_file: PidFile) {
+    info!("adopting trin process {}", pid_file.pid);
+    let state = app.state::<Mutex<AppData>>();
+    let mut state = state.lock().unwrap();
+    if transition(app, &mut state, NodeState::Starting).is_err() {
+        return;
+    }
+    state.readiness_probes = ReadinessProbes {
+        process_running: true,
+        rpc_bound: true,
+        beacon_synced: !pid_file
+            .trin_config
+            .subnetworks
+            .contains(&Subnetwork::Beacon),
+        ..Default::default()
+    };
+    update_readiness(app, &mut state);
+    let _ = transition(app, &mut state, NodeState::Running);
+
+    let status_token = CancellationToken::new();
+    spawn_status_probes(
+        app,
+        pid_file.pid,
+        &pid_file.trin_config,
+        status_token.clone(),
+    );
+    state.status_token = Some(status_token);
+    state.adopted_pid = Some(pid_file.pid);
+    state.trin_config = Some(pid_file.trin_config);
 }
@@ -1,24 +1,70 @@
 mod commands;
 mod types;
 mod utils;
-use crate::commands::{eth, trin};
+use crate::commands::{alerts, checkpoint, config, diagnostics, eth, exporter, profiles, trin};
+use crate::types::activity::ActivityTracker;
+use crate::types::alerts::AlertEngine;
+use crate::types::config::TrinConfig;
+use crate::types::crash_report::OutputBuffer;
+use crate::types::limits::AppliedLimits;
+use crate::types::log_buffer::LogBuffer;
 use crate::types::node::NodeStats;
+use crate::types::node_state::NodeState;
+use crate::types::readiness::{ReadinessProbes, ReadinessStage};
+use crate::types::stats_history::StatsHistory;
+use crate::types::storage::StorageTracker;
+use crate::types::supervisor::CrashSupervisor;
+use crate::utils::exporter::MetricsExporter;
+use crate::utils::limits::release_resource_limits;
 use std::sync::Mutex;
-use tauri::async_runtime::JoinHandle;
+use std::time::Instant;
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::Manager;
+use tauri::{Manager, RunEvent};
 use tauri_plugin_autostart::MacosLauncher;
-use tauri_plugin_shell::process::CommandChild;
+use tauri_plugin_shell::process::{CommandChild, TerminatedPayload};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Default)]
 struct AppData {
     trin_handle: Option<CommandChild>,
-    // todo: double check that we need this
-    log_handle: Option<JoinHandle<()>>,
-    // todo: double check that we need this
-    status_handle: Option<JoinHandle<()>>,
+    // pid of a trin process left behind by a previous run of the app, that we reattached to
+    adopted_pid: Option<u32>,
+    // cancels the task that reads trin's stdout & stderr
+    log_token: Option<CancellationToken>,
+    // cancels the probes that monitor the running trin node
+    status_token: Option<CancellationToken>,
+    // cancels a pending restart scheduled by the crash supervisor
+    restart_token: Option<CancellationToken>,
+    // the config trin was last launched with, used to restart it after a crash
+    trin_config: Option<TrinConfig>,
+    supervisor: CrashSupervisor,
+    // recent stdout & stderr of the running trin process, used for crash reports
+    trin_output: OutputBuffer,
+    // parsed log records of trin, for the in-app log viewer
+    trin_logs: LogBuffer,
+    // exit status of the trin process, once it has exited
+    trin_exit: Option<TerminatedPayload>,
+    // pid of a trin process that we stopped on purpose, so its exit isn't treated as a crash
+    expected_exit: Option<u32>,
     node_stats: NodeStats,
+    node_state: NodeState,
+    readiness: ReadinessStage,
+    readiness_probes: ReadinessProbes,
+    // the resource limits applied to the trin process we spawned
+    applied_limits: Option<AppliedLimits>,
+    // downsampled history of the node stats, kept for charts
+    stats_history: StatsHistory,
+    // rates & lifetime totals of trin's message counters
+    activity: ActivityTracker,
+    // recent sizes of trin's data dir, to project when it will be full
+    storage: StorageTracker,
+    // user-defined alert rules, evaluated on every stats sample
+    alerts: AlertEngine,
+    // when the node last became running, for the uptime reported by the exporter
+    running_since: Option<Instant>,
+    // the opt-in local metrics exporter, when it is enabled
+    exporter: Option<MetricsExporter>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -55,6 +101,8 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::new().build())
         // initializes the shell plugin which allows us to spawn child processes
         .plugin(tauri_plugin_shell::init())
+        // used to notify the user when an alert fires
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let menu = Menu::with_items(app, &[&quit_i])?;
@@ -62,6 +110,12 @@ pub fn run() {
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .menu_on_left_click(false)
+                .on_menu_event(|app, event| {
+                    // trin is shut down gracefully in the exit handler below
+                    if event.id.as_ref() == "quit" {
+                        app.exit(0);
+                    }
+                })
                 .on_tray_icon_event(|tray, event| match event {
                     TrayIconEvent::Click {
                         button: MouseButton::Left,
@@ -77,14 +131,49 @@ pub fn run() {
                     _ => {}
                 })
                 .build(app)?;
-            let app_data = AppData::default();
+            let mut app_data = AppData::default();
+            if let Ok(dir) = app.path().app_data_dir() {
+                app_data.stats_history = StatsHistory::load(&dir);
+                app_data.activity = ActivityTracker::load(&dir);
+                app_data.alerts = AlertEngine::load(&dir);
+            }
             app.manage(Mutex::new(app_data));
+            // deal with a trin process that outlived a previous run of the app,
+            // and launch trin if the user wants it running whenever the app is
+            tauri::async_runtime::spawn(trin::start_on_app_launch(app.handle().clone()));
+            tauri::async_runtime::spawn(exporter::restore_exporter(app.handle().clone()));
+            tauri::async_runtime::spawn(trin::save_stats_history(app.handle().clone()));
             Ok(())
         })
         // adds the commands that can be called from the frontend
         .invoke_handler(tauri::generate_handler![
             trin::launch_trin,
             trin::shutdown_trin,
+            trin::get_last_crash_report,
+            trin::get_node_status,
+            trin::get_node_readiness,
+            trin::check_trin_ports,
+            trin::get_applied_limits,
+            trin::get_stats_history,
+            trin::get_trin_logs,
+            trin::get_trin_log_targets,
+            trin::clear_trin_logs,
+            checkpoint::discover_trusted_block_root,
+            config::get_config,
+            config::set_config,
+            config::validate_config,
+            diagnostics::export_diagnostics,
+            alerts::get_alert_rules,
+            alerts::set_alert_rules,
+            alerts::get_alert_history,
+            alerts::clear_alert_history,
+            exporter::get_exporter_config,
+            exporter::set_exporter_config,
+            profiles::get_profiles,
+            profiles::create_profile,
+            profiles::clone_profile,
+            profiles::delete_profile,
+            profiles::activate_profile,
             eth::eth_getBlockByNumber,
             eth::eth_getBlockByHash,
             eth::eth_getBalance,
@@ -98,6 +187,51 @@ pub fn run() {
             }
             _ => {}
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // hold off exiting until trin has been shut down, so that it
+            // isn't orphaned or killed in the middle of writing to its db
+            RunEvent::ExitRequested { api, .. } => {
+                let state = app.state::<Mutex<AppData>>();
+                // trin's handle is taken as soon as a stop begins, so a stop that is in
+                // flight has to be waited for too
+                let trin_running = {
+                    let state = state.lock().unwrap();
+                    state.trin_handle.is_some()
+                        || state.adopted_pid.is_some()
+                        || state.node_state == NodeState::Stopping
+                };
+                if trin_running {
+                    api.prevent_exit();
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        trin::stop_trin_and_wait(&app).await;
+                        app.exit(0);
+                    });
+                }
+            }
+            // last resort, in case the app exits without going through the handler above
+            RunEvent::Exit => {
+                let state = app.state::<Mutex<AppData>>();
+                let (child, applied_limits) = {
+                    let mut state = state.lock().unwrap();
+                    (state.trin_handle.take(), state.applied_limits.take())
+                };
+                if let Some(child) = child {
+                    let _ = child.kill();
+                }
+                if let Some(applied_limits) = applied_limits {
+                    if let Err(e) = release_resource_limits(&applied_limits) {
+                        log::warn!("unable to release resource limits: {e}");
+                    }
+                }
+                // save the stats that were recorded since the last time they were saved
+                let writes = state.lock().unwrap().stats_history.take_writes();
+                if let Err(e) = writes.apply() {
+                    log::warn!("unable to save stats history: {e}");
+                }
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,279 @@
+use crate::types::node::{NodeStats, SubnetworkDataLog};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+const ACTIVITY_FILE: &str = "activity_totals.json";
+
+// the window that rates are averaged over, in seconds
+const RATE_WINDOW: u64 = 60;
+
+// the lifetime totals are saved at most this often, in seconds, so up to a
+// minute of activity is lost if the app is killed
+const SAVE_INTERVAL: u64 = 60;
+
+// one value for each of the message counters that trin reports per subnetwork
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetworkCounters<T> {
+    pub offers_in: T,
+    pub offers_out: T,
+    pub accepts_in: T,
+    pub accepts_out: T,
+    pub validations_in: T,
+    pub validations_out: T,
+}
+
+impl<T> SubnetworkCounters<T> {
+    fn from_fn(mut f: impl FnMut(&'static str, u64) -> T, log: &SubnetworkDataLog) -> Self {
+        Self {
+            offers_in: f("offersIn", log.offers_in as u64),
+            offers_out: f("offersOut", log.offers_out as u64),
+            accepts_in: f("acceptsIn", log.accepts_in as u64),
+            accepts_out: f("acceptsOut", log.accepts_out as u64),
+            validations_in: f("validationsIn", log.validations_in as u64),
+            validations_out: f("validationsOut", log.validations_out as u64),
+        }
+    }
+}
+
+// the current activity of a subnetwork, derived from trin's cumulative counters
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetworkActivity {
+    // per second, averaged over the last minute
+    pub rates: SubnetworkCounters<f64>,
+    // since the previous stats update
+    pub deltas: SubnetworkCounters<u64>,
+    // across every trin session since the app was installed
+    pub totals: SubnetworkCounters<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Counter {
+    // the last value reported by trin, None until the first value is seen
+    last: Option<u64>,
+    total: u64,
+    // (timestamp, total) pairs within the rate window
+    #[serde(skip)]
+    window: VecDeque<(u64, u64)>,
+}
+
+impl Counter {
+    // returns the increase since the last value. trin's counters start from zero
+    // whenever it restarts, so a value lower than the last one means it was reset
+    fn update(&mut self, timestamp: u64, value: u64) -> u64 {
+        let delta = match self.last {
+            Some(last) if value >= last => value - last,
+            Some(_) => value,
+            // the first value after the app started, with no saved baseline
+            None => 0,
+        };
+        self.last = Some(value);
+        self.total += delta;
+
+        self.window.push_back((timestamp, self.total));
+        while self
+            .window
+            .front()
+            .is_some_and(|(oldest, _)| *oldest + RATE_WINDOW < timestamp)
+        {
+            self.window.pop_front();
+        }
+        delta
+    }
+
+    fn rate(&self) -> f64 {
+        match (self.window.front(), self.window.back()) {
+            (Some((from, oldest)), Some((to, newest))) if to > from => {
+                (newest - oldest) as f64 / (to - from) as f64
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+// tracks the message counters of every subnetwork, correcting for trin restarts
+#[derive(Default)]
+pub struct ActivityTracker {
+    counters: HashMap<String, Counter>,
+    dir: Option<PathBuf>,
+    last_saved: u64,
+}
+
+impl ActivityTracker {
+    pub fn load(dir: &Path) -> Self {
+        let counters = std::fs::read_to_string(dir.join(ACTIVITY_FILE))
+            .ok()
+            .and_then(|counters| serde_json::from_str(&counters).ok())
+            .unwrap_or_default();
+        Self {
+            counters,
+            dir: Some(dir.to_path_buf()),
+            last_saved: 0,
+        }
+    }
+
+    // called when a new trin process is spawned, its counters start from zero. the stats
+    // still hold the counters of the previous process, they are cleared as well so that
+    // they aren't counted a second time before the new process reports its own
+    pub fn process_started(&mut self, stats: &mut NodeStats) {
+        stats.state_data = SubnetworkDataLog::default();
+        stats.history_data = SubnetworkDataLog::default();
+        stats.beacon_data = SubnetworkDataLog::default();
+        let default = SubnetworkDataLog::default();
+        for subnetwork in ["state", "history", "beacon"] {
+            // also creates the counters that haven't been seen yet
+            SubnetworkCounters::from_fn(
+                |name, value| {
+                    let key = format!("{subnetwork}.{name}");
+                    self.counters.entry(key).or_default().last = Some(value);
+                },
+                &default,
+            );
+        }
+    }
+
+    // returns a snapshot of the totals when they are due to be saved, it is written by
+    // the caller so that the tracker doesn't do any io while the app state is locked
+    pub fn update(&mut self, timestamp: u64, stats: &mut NodeStats) -> Option<ActivitySnapshot> {
+        stats.state_activity = self.update_subnetwork(timestamp, "state", &stats.state_data);
+        stats.history_activity = self.update_subnetwork(timestamp, "history", &stats.history_data);
+        stats.beacon_activity = self.update_subnetwork(timestamp, "beacon", &stats.beacon_data);
+
+        if timestamp < self.last_saved + SAVE_INTERVAL {
+            return None;
+        }
+        self.last_saved = timestamp;
+        self.snapshot()
+    }
+
+    fn update_subnetwork(
+        &mut self,
+        timestamp: u64,
+        subnetwork: &str,
+        log: &SubnetworkDataLog,
+    ) -> SubnetworkActivity {
+        let counters = &mut self.counters;
+        let deltas = SubnetworkCounters::from_fn(
+            |name, value| {
+                counters
+                    .entry(format!("{subnetwork}.{name}"))
+                    .or_default()
+                    .update(timestamp, value)
+            },
+            log,
+        );
+        let counter = |name| &counters[&format!("{subnetwork}.{name}")];
+        let default = SubnetworkDataLog::default();
+        SubnetworkActivity {
+            rates: SubnetworkCounters::from_fn(|name, _| counter(name).rate(), &default),
+            deltas,
+            totals: SubnetworkCounters::from_fn(|name, _| counter(name).total, &default),
+        }
+    }
+
+    // the totals as they would be saved, None if the tracker isn't backed by a file
+    pub fn snapshot(&self) -> Option<ActivitySnapshot> {
+        let dir = self.dir.clone()?;
+        match serde_json::to_string_pretty(&self.counters) {
+            Ok(counters) => Some(ActivitySnapshot { dir, counters }),
+            Err(e) => {
+                warn!("unable to serialize activity totals: {e}");
+                None
+            }
+        }
+    }
+}
+
+// the lifetime totals, ready to be written to disk
+pub struct ActivitySnapshot {
+    dir: PathBuf,
+    counters: String,
+}
+
+impl ActivitySnapshot {
+    pub fn save(self) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(ACTIVITY_FILE), self.counters).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history_offers(offers_in: u32) -> NodeStats {
+        let mut stats = NodeStats::default();
+        stats.history_data.offers_in = offers_in;
+        stats
+    }
+
+    #[test]
+    fn test_counter_resets_are_corrected() {
+        let mut tracker = ActivityTracker::default();
+        tracker.process_started(&mut NodeStats::default());
+        let mut timestamp = 1_730_000_000;
+        for offers_in in [10, 40, 70] {
+            let mut stats = history_offers(offers_in);
+            tracker.update(timestamp, &mut stats);
+            timestamp += 3;
+        }
+        let mut stats = history_offers(100);
+        tracker.update(timestamp, &mut stats);
+        assert_eq!(stats.history_activity.deltas.offers_in, 30);
+        assert_eq!(stats.history_activity.totals.offers_in, 100);
+        // 90 offers over 9 seconds
+        assert_eq!(stats.history_activity.rates.offers_in, 10.0);
+
+        // trin restarted, and its counter went back to zero
+        let mut stats = history_offers(5);
+        tracker.update(timestamp + 3, &mut stats);
+        assert_eq!(stats.history_activity.deltas.offers_in, 5);
+        assert_eq!(stats.history_activity.totals.offers_in, 105);
+        assert_eq!(stats.state_activity.totals.offers_in, 0);
+    }
+
+    #[test]
+    fn test_restart_with_stale_stats() {
+        let mut tracker = ActivityTracker::default();
+        let mut stats = history_offers(0);
+        tracker.process_started(&mut stats);
+        stats.history_data.offers_in = 50;
+        tracker.update(1_730_000_000, &mut stats);
+        assert_eq!(stats.history_activity.totals.offers_in, 50);
+
+        // trin was relaunched, and the stats are updated before it reports anything
+        tracker.process_started(&mut stats);
+        tracker.update(1_730_000_003, &mut stats);
+        assert_eq!(stats.history_activity.deltas.offers_in, 0);
+        assert_eq!(stats.history_activity.totals.offers_in, 50);
+
+        stats.history_data.offers_in = 7;
+        tracker.update(1_730_000_006, &mut stats);
+        assert_eq!(stats.history_activity.totals.offers_in, 57);
+    }
+
+    #[test]
+    fn test_totals_are_kept_across_sessions() {
+        let dir = std::env::temp_dir().join("trin-desktop-activity-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut tracker = ActivityTracker::load(&dir);
+        tracker.process_started(&mut NodeStats::default());
+        // the first update is saved right away
+        let snapshot = tracker.update(1_730_000_000, &mut history_offers(25));
+        snapshot.unwrap().save().unwrap();
+        assert!(tracker
+            .update(1_730_000_003, &mut history_offers(25))
+            .is_none());
+
+        // the app restarted, and adopted the trin process that was still running
+        let mut tracker = ActivityTracker::load(&dir);
+        let mut stats = history_offers(30);
+        tracker.update(1_730_000_100, &mut stats);
+        assert_eq!(stats.history_activity.deltas.offers_in, 5);
+        assert_eq!(stats.history_activity.totals.offers_in, 30);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
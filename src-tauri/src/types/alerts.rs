@@ -0,0 +1,465 @@
+use crate::types::config::Subnetwork;
+use crate::types::node::NodeStats;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+const ALERT_RULES_FILE: &str = "alert_rules.json";
+const ALERT_HISTORY_FILE: &str = "alert_history.json";
+
+// the number of alerts kept in the in-app alert history
+const ALERT_HISTORY_LEN: usize = 200;
+
+// what a rule checks on every stats sample
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AlertCondition {
+    // the measured size of trin's data dir is above this percentage of the configured storage
+    DiskUsageAbove {
+        percent: f64,
+    },
+    // the data radius of a subnetwork has shrunk below this percentage
+    RadiusBelow {
+        subnetwork: Subnetwork,
+        percent: f64,
+    },
+    // no content was validated, in any subnetwork, since the previous sample
+    NoValidations,
+    // the latest finalized block hasn't changed since the previous sample
+    FinalizedBlockStalled,
+    CpuAbove {
+        percent: f64,
+    },
+}
+
+impl AlertCondition {
+    // returns a description of the problem if the condition holds
+    fn check(&self, stats: &NodeStats, previous: &NodeStats, storage_mb: f64) -> Option<String> {
+        match self {
+            // the budget isn't used until the data dir has been measured
+            Self::DiskUsageAbove { percent } => stats
+                .storage
+                .budget_used
+                .filter(|used| used > percent)
+                .map(|used| format!("trin is using {used:.0}% of its {storage_mb} MB of storage")),
+            Self::RadiusBelow {
+                subnetwork,
+                percent,
+            } => {
+                let (name, data) = match subnetwork {
+                    Subnetwork::State => ("state", &stats.state_data),
+                    Subnetwork::History => ("history", &stats.history_data),
+                    Subnetwork::Beacon => ("beacon", &stats.beacon_data),
+                };
+                // a radius of zero means that no data has been reported yet
+                (data.radius > 0.0 && (data.radius as f64) < *percent)
+                    .then(|| format!("the {name} radius has shrunk to {}%", data.radius))
+            }
+            Self::NoValidations => {
+                let validations = |stats: &NodeStats| {
+                    [&stats.state_data, &stats.history_data, &stats.beacon_data]
+                        .iter()
+                        .map(|data| data.validations_in as u64)
+                        .sum::<u64>()
+                };
+                (validations(stats) == validations(previous))
+                    .then(|| "trin isn't validating any content".to_string())
+            }
+            // a block of zero means that trin hasn't reported one yet
+            Self::FinalizedBlockStalled => (stats.latest_finalized_block > 0
+                && stats.latest_finalized_block == previous.latest_finalized_block)
+                .then(|| {
+                    format!(
+                        "the finalized block is stuck at {}",
+                        stats.latest_finalized_block
+                    )
+                }),
+            Self::CpuAbove { percent } => (stats.cpu as f64 > *percent)
+                .then(|| format!("trin is using {:.0}% cpu", stats.cpu)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub condition: AlertCondition,
+    // how long the condition has to hold before the alert fires, so that a
+    // single bad sample doesn't trigger it
+    pub for_secs: u64,
+    // the minimum time between two notifications for this rule, while it keeps firing
+    pub cooldown_secs: u64,
+}
+
+impl AlertRule {
+    fn validate(&self) -> Result<(), String> {
+        let percent = match &self.condition {
+            AlertCondition::DiskUsageAbove { percent }
+            | AlertCondition::RadiusBelow { percent, .. }
+            | AlertCondition::CpuAbove { percent } => Some(*percent),
+            AlertCondition::NoValidations | AlertCondition::FinalizedBlockStalled => None,
+        };
+        // NaN fails this check as well
+        if percent.is_some_and(|percent| !(percent >= 0.0 && percent.is_finite())) {
+            return Err(format!("{}: the threshold can't be negative", self.name));
+        }
+        if self.for_secs == 0 || self.cooldown_secs == 0 {
+            return Err(format!(
+                "{}: the duration and cooldown must be at least a second",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    fn new(id: &str, name: &str, condition: AlertCondition, for_secs: u64) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            enabled: true,
+            condition,
+            for_secs,
+            cooldown_secs: 60 * 60,
+        }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new(
+                "disk-usage",
+                "Storage almost full",
+                AlertCondition::DiskUsageAbove { percent: 95.0 },
+                60,
+            ),
+            Self::new(
+                "history-radius",
+                "History radius shrinking",
+                AlertCondition::RadiusBelow {
+                    subnetwork: Subnetwork::History,
+                    percent: 1.0,
+                },
+                5 * 60,
+            ),
+            Self::new(
+                "no-validations",
+                "No validations",
+                AlertCondition::NoValidations,
+                30 * 60,
+            ),
+            // a new block is finalized every epoch, ie. every ~6.4 minutes
+            Self::new(
+                "finality-stalled",
+                "Finalized block not advancing",
+                AlertCondition::FinalizedBlockStalled,
+                20 * 60,
+            ),
+            Self::new(
+                "cpu",
+                "High cpu usage",
+                AlertCondition::CpuAbove { percent: 90.0 },
+                5 * 60,
+            ),
+        ]
+    }
+}
+
+// an entry of the alert history, also the payload of the "trin-alert" event
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub message: String,
+    // unix timestamp in seconds
+    pub timestamp: u64,
+    // false when the alert fires, true once its condition no longer holds
+    pub resolved: bool,
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    // when the condition started to hold, None while it doesn't
+    pending_since: Option<u64>,
+    firing: bool,
+    last_notified: Option<u64>,
+}
+
+// evaluates the alert rules on every stats sample
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    states: HashMap<String, RuleState>,
+    previous: Option<NodeStats>,
+    history: VecDeque<Alert>,
+    dir: Option<PathBuf>,
+}
+
+impl AlertEngine {
+    pub fn load(dir: &Path) -> Self {
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok();
+        let rules = read(ALERT_RULES_FILE)
+            .and_then(|rules| serde_json::from_str(&rules).ok())
+            .unwrap_or_else(AlertRule::defaults);
+        let history = read(ALERT_HISTORY_FILE)
+            .and_then(|history| serde_json::from_str(&history).ok())
+            .unwrap_or_default();
+        Self {
+            rules,
+            history,
+            dir: Some(dir.to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<AlertRule>) -> Result<(), String> {
+        let mut ids: Vec<&str> = rules.iter().map(|rule| rule.id.as_str()).collect();
+        ids.sort();
+        if ids.windows(2).any(|ids| ids[0] == ids[1]) {
+            return Err("alert rule ids must be unique".to_string());
+        }
+        for rule in &rules {
+            rule.validate()?;
+        }
+        self.rules = rules;
+        // rules that were changed start from scratch
+        self.states.clear();
+        self.save(ALERT_RULES_FILE, &self.rules)
+    }
+
+    pub fn history(&self) -> &VecDeque<Alert> {
+        &self.history
+    }
+
+    pub fn clear_history(&mut self) -> Result<(), String> {
+        self.history.clear();
+        self.save(ALERT_HISTORY_FILE, &self.history)
+    }
+
+    // called when trin stops or crashes, so that eg. a stalled finalized block isn't
+    // reported for the time trin wasn't running
+    pub fn reset(&mut self) {
+        self.states.clear();
+        self.previous = None;
+    }
+
+    // returns the alerts that fired or resolved with this sample. they are added to the
+    // history, which the caller saves with `history_snapshot` once the app state is released
+    pub fn evaluate(&mut self, timestamp: u64, stats: &NodeStats, storage_mb: f64) -> Vec<Alert> {
+        let Some(previous) = self.previous.replace(*stats) else {
+            return Vec::new();
+        };
+        let mut alerts = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule.enabled) {
+            let state = self.states.entry(rule.id.clone()).or_default();
+            let Some(message) = rule.condition.check(stats, &previous, storage_mb) else {
+                state.pending_since = None;
+                if state.firing {
+                    state.firing = false;
+                    alerts.push(Alert {
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        message: format!("{} is resolved", rule.name),
+                        timestamp,
+                        resolved: true,
+                    });
+                }
+                continue;
+            };
+            let pending_since = *state.pending_since.get_or_insert(timestamp);
+            if timestamp.saturating_sub(pending_since) < rule.for_secs {
+                continue;
+            }
+            state.firing = true;
+            let cooled_down = state.last_notified.is_none_or(|last_notified| {
+                timestamp.saturating_sub(last_notified) >= rule.cooldown_secs
+            });
+            if cooled_down {
+                state.last_notified = Some(timestamp);
+                alerts.push(Alert {
+                    rule_id: rule.id.clone(),
+                    rule_name: rule.name.clone(),
+                    message,
+                    timestamp,
+                    resolved: false,
+                });
+            }
+        }
+
+        if !alerts.is_empty() {
+            for alert in &alerts {
+                if self.history.len() == ALERT_HISTORY_LEN {
+                    self.history.pop_front();
+                }
+                self.history.push_back(alert.clone());
+            }
+        }
+        alerts
+    }
+
+    // the alert history as it would be saved, None if the engine isn't backed by a dir
+    pub fn history_snapshot(&self) -> Option<AlertHistorySnapshot> {
+        let dir = self.dir.clone()?;
+        match serde_json::to_string_pretty(&self.history) {
+            Ok(history) => Some(AlertHistorySnapshot { dir, history }),
+            Err(e) => {
+                warn!("unable to serialize alert history: {e}");
+                None
+            }
+        }
+    }
+
+    fn save(&self, file: &str, value: &impl Serialize) -> Result<(), String> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(file), json).map_err(|e| e.to_string())
+    }
+}
+
+// the alert history, ready to be written to disk
+pub struct AlertHistorySnapshot {
+    dir: PathBuf,
+    history: String,
+}
+
+impl AlertHistorySnapshot {
+    pub fn save(self) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(ALERT_HISTORY_FILE), self.history).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn engine(condition: AlertCondition, for_secs: u64, cooldown_secs: u64) -> AlertEngine {
+        let mut engine = AlertEngine::default();
+        let mut rule = AlertRule::new("test", "Test", condition, for_secs);
+        rule.cooldown_secs = cooldown_secs;
+        engine.set_rules(vec![rule]).unwrap();
+        engine
+    }
+
+    fn cpu(cpu: f32) -> NodeStats {
+        NodeStats {
+            cpu,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_alerts_are_debounced_and_cooled_down() {
+        let mut engine = engine(AlertCondition::CpuAbove { percent: 90.0 }, 30, 60);
+        let mut fired = Vec::new();
+        for (timestamp, usage) in [
+            (0, 10.0),
+            (10, 95.0),
+            // a single spike doesn't fire the alert
+            (20, 10.0),
+            (30, 95.0),
+            (50, 95.0),
+            (60, 95.0),
+            (70, 95.0),
+            (100, 95.0),
+            (130, 95.0),
+            (140, 20.0),
+        ] {
+            for alert in engine.evaluate(timestamp, &cpu(usage), 2000.0) {
+                fired.push((alert.timestamp, alert.resolved));
+            }
+        }
+        assert_eq!(fired, vec![(60, false), (130, false), (140, true)]);
+        assert_eq!(engine.history().len(), 3);
+    }
+
+    #[rstest]
+    #[case(AlertCondition::DiskUsageAbove { percent: 90.0 }, true)]
+    #[case(AlertCondition::DiskUsageAbove { percent: 99.0 }, false)]
+    #[case(AlertCondition::RadiusBelow { subnetwork: Subnetwork::History, percent: 10.0 }, true)]
+    #[case(AlertCondition::RadiusBelow { subnetwork: Subnetwork::State, percent: 10.0 }, false)]
+    #[case(AlertCondition::NoValidations, false)]
+    #[case(AlertCondition::FinalizedBlockStalled, true)]
+    fn test_alert_conditions(#[case] condition: AlertCondition, #[case] holds: bool) {
+        let mut previous = NodeStats::default();
+        previous.history_data.validations_in = 10;
+        previous.latest_finalized_block = 21_000_000;
+        let mut stats = previous;
+        stats.storage.budget_used = Some(95.0);
+        stats.history_data.radius = 5.0;
+        stats.state_data.radius = 100.0;
+        stats.history_data.validations_in = 12;
+        assert_eq!(condition.check(&stats, &previous, 2000.0).is_some(), holds);
+    }
+
+    #[test]
+    fn test_conditions_wait_for_data() {
+        let stats = NodeStats::default();
+        for condition in [
+            AlertCondition::DiskUsageAbove { percent: 0.0 },
+            AlertCondition::FinalizedBlockStalled,
+        ] {
+            assert!(condition.check(&stats, &stats, 2000.0).is_none());
+        }
+    }
+
+    #[test]
+    fn test_clock_going_backwards() {
+        let mut engine = engine(AlertCondition::CpuAbove { percent: 90.0 }, 30, 60);
+        for (timestamp, usage) in [(100, 95.0), (130, 95.0), (160, 95.0)] {
+            engine.evaluate(timestamp, &cpu(usage), 2000.0);
+        }
+        // the clock was set back, which shouldn't panic or renotify
+        assert!(engine.evaluate(50, &cpu(95.0), 2000.0).is_empty());
+        assert_eq!(engine.history().len(), 1);
+    }
+
+    #[rstest]
+    #[case(AlertCondition::CpuAbove { percent: -1.0 }, 60)]
+    #[case(AlertCondition::DiskUsageAbove { percent: f64::NAN }, 60)]
+    #[case(AlertCondition::RadiusBelow { subnetwork: Subnetwork::State, percent: f64::INFINITY }, 60)]
+    #[case(AlertCondition::NoValidations, 0)]
+    fn test_invalid_rules_are_refused(#[case] condition: AlertCondition, #[case] for_secs: u64) {
+        let mut engine = AlertEngine::default();
+        let rule = AlertRule::new("test", "Test", condition, for_secs);
+        assert!(engine.set_rules(vec![rule]).is_err());
+        assert!(engine.set_rules(AlertRule::defaults()).is_ok());
+    }
+
+    #[test]
+    fn test_alert_rules_are_persisted() {
+        let dir = std::env::temp_dir().join("trin-desktop-alerts-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut engine = AlertEngine::load(&dir);
+        assert_eq!(engine.rules(), AlertRule::defaults().as_slice());
+
+        let mut rules = AlertRule::defaults();
+        rules[0].enabled = false;
+        engine.set_rules(rules.clone()).unwrap();
+        assert_eq!(AlertEngine::load(&dir).rules(), rules.as_slice());
+
+        rules[1].id = rules[0].id.clone();
+        assert!(engine.set_rules(rules).is_err());
+
+        let mut history = engine.history_snapshot().unwrap();
+        history.history = "[]".to_string();
+        history.save().unwrap();
+        assert!(AlertEngine::load(&dir).history().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
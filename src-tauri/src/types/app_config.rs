@@ -0,0 +1,472 @@
+use crate::types::checkpoint::CheckpointConfig;
+use crate::types::config::{OrphanPolicy, TrinConfig};
+use crate::types::exporter::ExporterConfig;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+// bumped whenever the layout of config.json changes, so older files can be migrated
+pub const CONFIG_VERSION: u64 = 2;
+const VERSION_KEY: &str = "configVersion";
+
+// the profile that the config of older versions is moved into
+pub const DEFAULT_PROFILE: &str = "default";
+
+// everything that's stored in config.json. the trin config is flattened into it,
+// so every field is a top-level key of the store, like the frontend used to write them
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppConfig {
+    #[serde(flatten)]
+    pub trin: TrinConfig,
+    // launch the app when the user logs in
+    #[serde(default = "default_autostart")]
+    pub autostart: bool,
+    // launch trin as soon as the app starts, without waiting for the window
+    #[serde(default)]
+    pub autostart_node: bool,
+    #[serde(default)]
+    pub orphaned_node_policy: OrphanPolicy,
+    #[serde(default)]
+    pub metrics_exporter: ExporterConfig,
+    // where trusted block roots are discovered
+    #[serde(default)]
+    pub checkpoint_sync: CheckpointConfig,
+    // named trin configs that the user can switch between. the flattened trin config
+    // above is the one of the active profile
+    #[serde(default)]
+    pub profiles: Vec<ConfigProfile>,
+    #[serde(default = "default_profile")]
+    pub active_profile: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigProfile {
+    pub name: String,
+    pub config: TrinConfig,
+}
+
+// the profiles, as shown to the frontend
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<ConfigProfile>,
+}
+
+// the name of a profile, turned into something that can be used as a dir name
+pub fn profile_dir_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+fn default_autostart() -> bool {
+    true
+}
+
+impl AppConfig {
+    // reads the config from the entries of the store, migrating older layouts first. values
+    // that can't be read are replaced by their defaults, otherwise a single bad value would
+    // make the whole config unreadable, and it couldn't be repaired from the app
+    pub fn from_entries(mut entries: Map<String, Value>) -> Result<Self, String> {
+        migrate(&mut entries);
+        match serde_json::from_value(Value::Object(entries.clone())) {
+            Ok(config) => Ok(config),
+            Err(e) => {
+                warn!("the config has invalid values, using their defaults instead: {e}");
+                let entries = drop_invalid_entries(entries);
+                serde_json::from_value(Value::Object(entries)).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&ConfigProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn add_profile(&mut self, profile: ConfigProfile) -> Result<(), String> {
+        let dir_name = profile_dir_name(&profile.name);
+        if dir_name.is_empty() {
+            return Err("a profile needs a name with letters or numbers".to_string());
+        }
+        // the dir name has to be unique as well, since it names the profile's data dir
+        if let Some(existing) = self
+            .profiles
+            .iter()
+            .find(|existing| profile_dir_name(&existing.name) == dir_name)
+        {
+            return Err(format!(
+                "{:?} is too similar to the existing profile {:?}",
+                profile.name, existing.name
+            ));
+        }
+        self.check_data_dir(&profile)?;
+        self.profiles.push(profile);
+        Ok(())
+    }
+
+    // profiles can't share a data dir, trin would mix their content in one database.
+    // profiles without one share trin's default data dir
+    fn check_data_dir(&self, profile: &ConfigProfile) -> Result<(), String> {
+        let data_dir = profile.config.dataDir.as_deref().map(Path::new);
+        match self.profiles.iter().find(|other| {
+            other.name != profile.name && other.config.dataDir.as_deref().map(Path::new) == data_dir
+        }) {
+            Some(other) => Err(format!(
+                "the profile {:?} already uses this data dir",
+                other.name
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn remove_profile(&mut self, name: &str) -> Result<ConfigProfile, String> {
+        if name == self.active_profile {
+            return Err(format!("{name:?} can't be deleted while it is active"));
+        }
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| format!("there is no profile named {name:?}"))?;
+        Ok(self.profiles.remove(index))
+    }
+
+    // makes the profile's trin config the one that trin is launched with
+    pub fn activate_profile(&mut self, name: &str) -> Result<(), String> {
+        let profile = self
+            .profile(name)
+            .ok_or_else(|| format!("there is no profile named {name:?}"))?;
+        let (name, config) = (profile.name.clone(), profile.config.clone());
+        self.trin = config;
+        self.active_profile = name;
+        Ok(())
+    }
+
+    // copies the trin config into the active profile, after it was edited
+    pub fn sync_active_profile(&mut self) -> Result<(), String> {
+        let profile = ConfigProfile {
+            name: self.active_profile.clone(),
+            config: self.trin.clone(),
+        };
+        self.check_data_dir(&profile)?;
+        match self
+            .profiles
+            .iter_mut()
+            .find(|existing| existing.name == profile.name)
+        {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        Ok(())
+    }
+
+    pub fn to_entries(&self) -> Result<Map<String, Value>, String> {
+        let Value::Object(mut entries) = serde_json::to_value(self).map_err(|e| e.to_string())?
+        else {
+            return Err("the config isn't a json object".to_string());
+        };
+        entries.insert(VERSION_KEY.to_string(), CONFIG_VERSION.into());
+        Ok(entries)
+    }
+}
+
+// brings the entries of an older config.json up to date, returns whether anything changed
+pub fn migrate(entries: &mut Map<String, Value>) -> bool {
+    let version = entries
+        .get(VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+    if version >= CONFIG_VERSION {
+        return false;
+    }
+    if version < 1 {
+        migrate_v0(entries);
+    }
+    if version < 2 {
+        migrate_v1(entries);
+    }
+    entries.insert(VERSION_KEY.to_string(), CONFIG_VERSION.into());
+    true
+}
+
+// version 0 was written by the frontend. it is empty until the window first opened,
+// may have numbers from text inputs stored as strings, and its "autostart" key also
+// meant that the node was launched as soon as the window opened
+fn migrate_v0(entries: &mut Map<String, Value>) {
+    let defaults = [
+        ("httpPort", json!(8545)),
+        ("storage", json!(2000)),
+        ("trustedBlockRoot", json!("0x")),
+        ("autostart", json!(true)),
+    ];
+    for (key, default) in defaults {
+        let value = entries.entry(key).or_insert(Value::Null);
+        if value.is_null() {
+            *value = default;
+        }
+    }
+
+    let numbers = [
+        "httpPort",
+        "storage",
+        "metricsPort",
+        "discoveryPort",
+        "niceness",
+        "ioPriority",
+        "addressSpaceLimit",
+        "cgroupCpuQuota",
+        "cgroupMemoryMax",
+    ];
+    for key in numbers {
+        let Some(Value::String(text)) = entries.get(key) else {
+            continue;
+        };
+        if let Ok(number) = text.trim().parse::<i64>() {
+            entries.insert(key.to_string(), number.into());
+        }
+    }
+
+    let autostart = entries["autostart"].as_bool().unwrap_or(true);
+    entries.entry("autostartNode").or_insert(autostart.into());
+}
+
+// version 1 had a single trin config, which becomes the default profile. that profile
+// keeps using trin's default data dir, so no data has to be moved
+fn migrate_v1(entries: &mut Map<String, Value>) {
+    let config: Map<String, Value> = entries
+        .iter()
+        .filter(|(key, _)| !APP_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let profile = json!({ "name": DEFAULT_PROFILE, "config": config });
+    entries.insert("profiles".to_string(), json!([profile]));
+    entries.insert("activeProfile".to_string(), json!(DEFAULT_PROFILE));
+}
+
+// the entries of a new config.json
+fn default_entries() -> Map<String, Value> {
+    let mut entries = Map::new();
+    migrate(&mut entries);
+    entries
+}
+
+// replaces the values that can't be deserialized by their defaults, the trin configs of
+// the profiles are checked one by one, so a bad value doesn't take the whole profile along
+fn drop_invalid_entries(mut entries: Map<String, Value>) -> Map<String, Value> {
+    let defaults = default_entries();
+    let trin_defaults: Map<String, Value> = defaults
+        .iter()
+        .filter(|(key, _)| !APP_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    if let Some(Value::Array(profiles)) = entries.get_mut("profiles") {
+        profiles.retain_mut(|profile| {
+            let Some(config) = profile.get_mut("config").and_then(Value::as_object_mut) else {
+                return false;
+            };
+            *config = valid_entries::<TrinConfig>(std::mem::take(config), &trin_defaults);
+            serde_json::from_value::<ConfigProfile>(profile.clone()).is_ok()
+        });
+    }
+    valid_entries::<AppConfig>(entries, &defaults)
+}
+
+// the defaults, overridden by the entries that deserialize on their own
+fn valid_entries<T: DeserializeOwned>(
+    entries: Map<String, Value>,
+    defaults: &Map<String, Value>,
+) -> Map<String, Value> {
+    let mut valid = defaults.clone();
+    for (key, value) in entries {
+        let mut candidate = defaults.clone();
+        candidate.insert(key.clone(), value.clone());
+        if serde_json::from_value::<T>(Value::Object(candidate)).is_ok() {
+            valid.insert(key, value);
+        } else {
+            warn!("ignoring the invalid config value {key:?}: {value}");
+        }
+    }
+    valid
+}
+
+// the keys of config.json that aren't part of the trin config
+const APP_KEYS: [&str; 8] = [
+    VERSION_KEY,
+    "autostart",
+    "autostartNode",
+    "orphanedNodePolicy",
+    "metricsExporter",
+    "checkpointSync",
+    "profiles",
+    "activeProfile",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(json: Value) -> Map<String, Value> {
+        json.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_migrates_empty_store() {
+        let config = AppConfig::from_entries(Map::new()).unwrap();
+        assert_eq!(config.trin.httpPort, 8545);
+        assert_eq!(config.trin.storage, 2000);
+        assert!(config.autostart);
+        assert!(config.autostart_node);
+        assert_eq!(config.orphaned_node_policy, OrphanPolicy::Adopt);
+        assert!(!config.metrics_exporter.enabled);
+    }
+
+    #[test]
+    fn test_migrates_frontend_store() {
+        let mut store = entries(json!({
+            "httpPort": "8546",
+            "storage": 5000,
+            "autostart": false,
+            "trustedBlockRoot": "0x",
+            "orphanedNodePolicy": "terminate",
+            "niceness": "5",
+        }));
+        assert!(migrate(&mut store));
+        assert!(!migrate(&mut store));
+
+        let config = AppConfig::from_entries(store).unwrap();
+        assert_eq!(config.trin.httpPort, 8546);
+        assert_eq!(config.trin.storage, 5000);
+        assert_eq!(config.trin.niceness, 5);
+        assert!(!config.autostart);
+        assert!(!config.autostart_node);
+        assert_eq!(config.orphaned_node_policy, OrphanPolicy::Terminate);
+    }
+
+    #[test]
+    fn test_migrates_to_default_profile() {
+        let config = AppConfig::from_entries(entries(json!({
+            "configVersion": 1,
+            "httpPort": 8546,
+            "storage": 5000,
+            "trustedBlockRoot": "0x",
+            "autostart": true,
+            "autostartNode": false,
+        })))
+        .unwrap();
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert_eq!(config.profiles.len(), 1);
+        let profile = config.profile(DEFAULT_PROFILE).unwrap();
+        assert_eq!(profile.config.httpPort, 8546);
+        assert_eq!(profile.config.storage, 5000);
+        assert_eq!(profile.config.dataDir, None);
+    }
+
+    #[test]
+    fn test_invalid_values_use_defaults() {
+        let config = AppConfig::from_entries(entries(json!({
+            "configVersion": 2,
+            "httpPort": "",
+            "storage": 3000,
+            "trustedBlockRoot": "0x",
+            "metricsPort": null,
+            "autostartNode": false,
+            "activeProfile": "default",
+            "profiles": [
+                { "name": "default", "config": { "httpPort": 8546, "storage": "lots" } },
+                { "config": {} },
+            ],
+        })))
+        .unwrap();
+        assert_eq!(config.trin.httpPort, 8545);
+        assert_eq!(config.trin.storage, 3000);
+        assert_eq!(config.trin.metricsPort, 9100);
+        assert!(!config.autostart_node);
+        // a profile without a name can't be kept, the other one keeps its valid values
+        assert_eq!(config.profiles.len(), 1);
+        let profile = config.profile(DEFAULT_PROFILE).unwrap();
+        assert_eq!(profile.config.httpPort, 8546);
+        assert_eq!(profile.config.storage, 2000);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut config = AppConfig::from_entries(Map::new()).unwrap();
+        let mut laptop = config.trin.clone();
+        laptop.storage = 500;
+        laptop.dataDir = Some("/data/laptop".to_string());
+        config
+            .add_profile(ConfigProfile {
+                name: "Laptop".to_string(),
+                config: laptop,
+            })
+            .unwrap();
+        let duplicate = ConfigProfile {
+            name: " laptop ".to_string(),
+            config: config.trin.clone(),
+        };
+        assert!(config.add_profile(duplicate).is_err());
+        // the default profile uses trin's default data dir already
+        let shared = ConfigProfile {
+            name: "Desktop".to_string(),
+            config: config.trin.clone(),
+        };
+        assert!(config.add_profile(shared).is_err());
+
+        config.activate_profile("Laptop").unwrap();
+        assert_eq!(config.trin.storage, 500);
+        assert!(config.remove_profile("Laptop").is_err());
+
+        // edits of the trin config end up in the active profile
+        config.trin.storage = 600;
+        config.sync_active_profile().unwrap();
+        assert_eq!(config.profile("Laptop").unwrap().config.storage, 600);
+        config.trin.dataDir = None;
+        assert!(config.sync_active_profile().is_err());
+        config.trin.dataDir = Some("/data/laptop/".to_string());
+        assert!(config.sync_active_profile().is_ok());
+
+        config.activate_profile(DEFAULT_PROFILE).unwrap();
+        assert_eq!(config.trin.storage, 2000);
+        assert!(config.remove_profile("Laptop").is_ok());
+        assert!(config.activate_profile("Laptop").is_err());
+    }
+
+    #[test]
+    fn test_profile_dir_name() {
+        assert_eq!(
+            profile_dir_name("Big Storage (testnet)"),
+            "big-storage--testnet"
+        );
+        assert_eq!(profile_dir_name("  "), "");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut config = AppConfig::from_entries(Map::new()).unwrap();
+        config.autostart_node = false;
+        config.trin.storage = 3000;
+        let entries = config.to_entries().unwrap();
+        assert_eq!(entries[VERSION_KEY], json!(CONFIG_VERSION));
+        assert_eq!(entries["storage"], json!(3000));
+        assert_eq!(entries["autostartNode"], json!(false));
+
+        // the current version isn't migrated again, so autostartNode stays off
+        let config = AppConfig::from_entries(entries).unwrap();
+        assert!(config.autostart);
+        assert!(!config.autostart_node);
+        assert_eq!(config.trin.storage, 3000);
+    }
+}
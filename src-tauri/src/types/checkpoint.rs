@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// the length of the beacon chain's slots and epochs. its genesis time depends on the
+// network, so it is asked from the endpoints
+const SECONDS_PER_SLOT: u64 = 12;
+const SLOTS_PER_EPOCH: u64 = 32;
+
+// the beacon api endpoints that are asked for a finalized block root, they are stored
+// under the "checkpointSync" key of config.json and can be replaced by the user
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointConfig {
+    #[serde(default = "default_endpoints")]
+    pub endpoints: Vec<String>,
+    // how many endpoints have to report the same block root before it is trusted
+    #[serde(default = "default_min_agreement")]
+    pub min_agreement: usize,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: default_endpoints(),
+            min_agreement: default_min_agreement(),
+        }
+    }
+}
+
+// public checkpoint sync providers, from https://eth-clients.github.io/checkpoint-sync-endpoints
+fn default_endpoints() -> Vec<String> {
+    [
+        "https://mainnet.checkpoint.sigp.io",
+        "https://sync-mainnet.beaconcha.in",
+        "https://beaconstate.info",
+        "https://mainnet-checkpoint-sync.attestant.io",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_min_agreement() -> usize {
+    2
+}
+
+impl CheckpointConfig {
+    // a single endpoint could hand out any root it likes, so at least two have to agree
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_agreement < 2 {
+            return Err("at least 2 endpoints have to agree on a block root".to_string());
+        }
+        if self.endpoints.len() < self.min_agreement {
+            return Err(format!(
+                "{} endpoints can't reach an agreement of {}",
+                self.endpoints.len(),
+                self.min_agreement
+            ));
+        }
+        Ok(())
+    }
+}
+
+// a block header, as returned by `/eth/v1/beacon/headers/{block_id}`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BlockHeader {
+    pub root: String,
+    pub slot: u64,
+}
+
+impl BlockHeader {
+    pub fn parse(body: &str) -> Result<Self, String> {
+        let response: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+        let data = &response["data"];
+        let root = data["root"]
+            .as_str()
+            .filter(|root| is_block_root(root))
+            .ok_or("the response doesn't contain a block root")?;
+        // the beacon api encodes integers as strings
+        let slot = data["header"]["message"]["slot"]
+            .as_str()
+            .and_then(|slot| slot.parse().ok())
+            .ok_or("the response doesn't contain a slot")?;
+        Ok(Self {
+            root: root.to_lowercase(),
+            slot,
+        })
+    }
+}
+
+// the genesis time of the endpoint's network, as returned by `/eth/v1/beacon/genesis`
+pub fn parse_genesis_time(body: &str) -> Result<u64, String> {
+    let response: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    response["data"]["genesis_time"]
+        .as_str()
+        .and_then(|genesis_time| genesis_time.parse().ok())
+        .ok_or("the response doesn't contain a genesis time".to_string())
+}
+
+// picks the genesis time that a majority of the endpoints that responded reported, and at
+// least `min_agreement` of them, so that a single endpoint can't skew the checkpoint's age
+pub fn agree_on_genesis_time(
+    responses: &[(String, Result<u64, String>)],
+    min_agreement: usize,
+) -> Result<u64, String> {
+    let mut votes: HashMap<u64, usize> = HashMap::new();
+    for genesis_time in responses
+        .iter()
+        .filter_map(|(_, response)| response.as_ref().ok())
+    {
+        *votes.entry(*genesis_time).or_default() += 1;
+    }
+    let responded: usize = votes.values().sum();
+    let required = min_agreement.max(responded / 2 + 1);
+    votes
+        .into_iter()
+        .find(|(_, count)| *count >= required)
+        .map(|(genesis_time, _)| genesis_time)
+        .ok_or_else(|| {
+            let responses: Vec<_> = responses
+                .iter()
+                .map(|(endpoint, response)| match response {
+                    Ok(genesis_time) => format!("{endpoint} reported {genesis_time}"),
+                    Err(e) => format!("{endpoint} failed: {e}"),
+                })
+                .collect();
+            format!(
+                "fewer than {required} endpoints agreed on a genesis time: {}",
+                responses.join(", ")
+            )
+        })
+}
+
+fn is_block_root(root: &str) -> bool {
+    root.strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// a finalized block root that enough endpoints agreed on, shown to the user before
+// it is used as trin's trusted block root
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub root: String,
+    pub slot: u64,
+    pub epoch: u64,
+    // seconds since the slot started, at the time of the discovery
+    pub age_secs: u64,
+    // the endpoints that reported this root
+    pub sources: Vec<String>,
+    // the endpoints that failed or reported another root, with the reason
+    pub disagreements: Vec<(String, String)>,
+}
+
+impl Checkpoint {
+    // picks the header that a majority of the endpoints that responded reported, and at
+    // least `min_agreement` of them. a few endpoints that report a newer root can't outvote
+    // the rest, and a root that conflicts with another well supported one isn't trusted.
+    // `genesis_time` & `now` are in seconds since the unix epoch
+    pub fn agree(
+        responses: &[(String, Result<BlockHeader, String>)],
+        min_agreement: usize,
+        genesis_time: u64,
+        now: u64,
+    ) -> Result<Self, String> {
+        let mut votes: HashMap<&BlockHeader, Vec<&str>> = HashMap::new();
+        for (endpoint, header) in responses {
+            if let Ok(header) = header {
+                votes.entry(header).or_default().push(endpoint);
+            }
+        }
+        let responded: usize = votes.values().map(Vec::len).sum();
+        let required = min_agreement.max(responded / 2 + 1);
+        // there can only be one majority
+        let Some((header, sources)) = votes.iter().find(|(_, sources)| sources.len() >= required)
+        else {
+            return Err(format!(
+                "fewer than {required} endpoints agreed on a block root: {}",
+                describe_responses(responses)
+            ));
+        };
+        if let Some((conflict, _)) = votes.iter().find(|(other, other_sources)| {
+            other.slot == header.slot
+                && other.root != header.root
+                && other_sources.len() >= min_agreement
+        }) {
+            return Err(format!(
+                "endpoints reported conflicting block roots for slot {}: {} and {}",
+                header.slot, header.root, conflict.root
+            ));
+        }
+
+        let disagreements = responses
+            .iter()
+            .filter(|(endpoint, _)| !sources.contains(&endpoint.as_str()))
+            .map(|(endpoint, response)| {
+                let reason = match response {
+                    Ok(other) => format!("reported {} at slot {}", other.root, other.slot),
+                    Err(e) => e.clone(),
+                };
+                (endpoint.clone(), reason)
+            })
+            .collect();
+        Ok(Self {
+            root: header.root.clone(),
+            slot: header.slot,
+            epoch: header.slot / SLOTS_PER_EPOCH,
+            age_secs: now.saturating_sub(slot_time(genesis_time, header.slot)),
+            sources: sources.iter().map(|source| source.to_string()).collect(),
+            disagreements,
+        })
+    }
+}
+
+// the time a slot started, in seconds since the unix epoch
+pub fn slot_time(genesis_time: u64, slot: u64) -> u64 {
+    genesis_time + slot * SECONDS_PER_SLOT
+}
+
+fn describe_responses(responses: &[(String, Result<BlockHeader, String>)]) -> String {
+    responses
+        .iter()
+        .map(|(endpoint, response)| match response {
+            Ok(header) => format!(
+                "{endpoint} reported {} at slot {}",
+                header.root, header.slot
+            ),
+            Err(e) => format!("{endpoint} failed: {e}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET_GENESIS_TIME: u64 = 1606824023;
+
+    fn header(root: char, slot: u64) -> BlockHeader {
+        BlockHeader {
+            root: format!("0x{}", root.to_string().repeat(64)),
+            slot,
+        }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let body = serde_json::json!({
+            "execution_optimistic": false,
+            "finalized": true,
+            "data": {
+                "root": format!("0x{}", "AB".repeat(32)),
+                "canonical": true,
+                "header": { "message": { "slot": "10240032" } },
+            },
+        });
+        let header = BlockHeader::parse(&body.to_string()).unwrap();
+        assert_eq!(header.root, format!("0x{}", "ab".repeat(32)));
+        assert_eq!(header.slot, 10240032);
+
+        assert!(BlockHeader::parse(r#"{"data": {"root": "0x12"}}"#).is_err());
+        assert!(BlockHeader::parse("not found").is_err());
+    }
+
+    #[test]
+    fn test_agree() {
+        let responses = vec![
+            ("a".to_string(), Ok(header('1', 64))),
+            ("b".to_string(), Ok(header('2', 96))),
+            ("c".to_string(), Ok(header('1', 64))),
+            ("d".to_string(), Err("timed out".to_string())),
+        ];
+        let now = slot_time(MAINNET_GENESIS_TIME, 64) + 60;
+        let checkpoint = Checkpoint::agree(&responses, 2, MAINNET_GENESIS_TIME, now).unwrap();
+        assert_eq!(checkpoint.root, header('1', 64).root);
+        assert_eq!((checkpoint.slot, checkpoint.epoch), (64, 2));
+        assert_eq!(checkpoint.age_secs, 60);
+        assert_eq!(checkpoint.sources, ["a", "c"]);
+        assert_eq!(checkpoint.disagreements.len(), 2);
+
+        // a single endpoint isn't trusted on its own, however new its root is
+        assert!(Checkpoint::agree(&responses, 3, MAINNET_GENESIS_TIME, now).is_err());
+        assert!(Checkpoint::agree(&responses[1..2], 2, MAINNET_GENESIS_TIME, now).is_err());
+    }
+
+    #[test]
+    fn test_split_vote_is_refused() {
+        let responses = vec![
+            ("a".to_string(), Ok(header('1', 64))),
+            ("b".to_string(), Ok(header('1', 64))),
+            ("c".to_string(), Ok(header('2', 64))),
+            ("d".to_string(), Ok(header('2', 64))),
+        ];
+        let now = slot_time(MAINNET_GENESIS_TIME, 64);
+        assert!(Checkpoint::agree(&responses, 2, MAINNET_GENESIS_TIME, now).is_err());
+
+        // a majority at the same slot isn't trusted either, if the other root is well supported
+        let mut responses = responses;
+        responses.push(("e".to_string(), Ok(header('1', 64))));
+        let error = Checkpoint::agree(&responses, 2, MAINNET_GENESIS_TIME, now).unwrap_err();
+        assert!(error.contains("conflicting"));
+    }
+
+    #[test]
+    fn test_newer_minority_is_outvoted() {
+        let responses = vec![
+            ("a".to_string(), Ok(header('2', 96))),
+            ("b".to_string(), Ok(header('2', 96))),
+            ("c".to_string(), Ok(header('1', 64))),
+            ("d".to_string(), Ok(header('1', 64))),
+            ("e".to_string(), Ok(header('1', 64))),
+        ];
+        let now = slot_time(MAINNET_GENESIS_TIME, 96);
+        let checkpoint = Checkpoint::agree(&responses, 2, MAINNET_GENESIS_TIME, now).unwrap();
+        assert_eq!(checkpoint.root, header('1', 64).root);
+        assert_eq!(checkpoint.sources, ["c", "d", "e"]);
+    }
+
+    #[test]
+    fn test_agree_on_genesis_time() {
+        let body =
+            r#"{"data": {"genesis_time": "1655733600", "genesis_fork_version": "0x90000069"}}"#;
+        assert_eq!(parse_genesis_time(body), Ok(1655733600));
+        assert!(parse_genesis_time(r#"{"data": {}}"#).is_err());
+
+        let responses = vec![
+            ("a".to_string(), Ok(1655733600)),
+            ("b".to_string(), Ok(MAINNET_GENESIS_TIME)),
+            ("c".to_string(), Ok(1655733600)),
+            ("d".to_string(), Err("timed out".to_string())),
+        ];
+        assert_eq!(agree_on_genesis_time(&responses, 2), Ok(1655733600));
+        assert!(agree_on_genesis_time(&responses, 3).is_err());
+        assert!(agree_on_genesis_time(&responses[..2], 2).is_err());
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(CheckpointConfig::default().validate().is_ok());
+        let config = CheckpointConfig {
+            endpoints: vec!["http://127.0.0.1:5052".to_string()],
+            min_agreement: 2,
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,8 +1,27 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::Path;
+
+// trin's --mb flag is in megabytes of 1000 * 1000 bytes, every other size in the
+// config is in the same unit
+pub const BYTES_PER_MB: u64 = 1_000_000;
+
+// the smallest storage that's accepted, in megabytes
+pub const MIN_STORAGE_MB: usize = 100;
+
+// the smallest resource limits that trin can run with, in megabytes. trin reserves a
+// lot more virtual memory than it uses, so its address space needs plenty of room
+const MIN_ADDRESS_SPACE_MB: u64 = 4096;
+const MIN_CGROUP_MEMORY_MB: u64 = 256;
+
+// the udp port that trin binds to for discv5, unless told otherwise
+pub const DEFAULT_DISCOVERY_PORT: u16 = 9009;
 
 // the user-defined configuration for the trin node,
 // this is passed from the frontend
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[allow(non_snake_case)]
 pub struct TrinConfig {
     // args received from the frontend must be camelCase
@@ -10,4 +29,528 @@ pub struct TrinConfig {
     pub httpPort: usize,
     pub storage: usize,
     pub trustedBlockRoot: String,
+    // seconds to wait for trin to flush its db after SIGTERM, before killing it
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdownTimeout: u64,
+    // local port that trin exposes its prometheus metrics on
+    #[serde(default = "default_metrics_port")]
+    pub metricsPort: u16,
+    // resource limits applied to the trin process, only supported on linux.
+    // niceness ranges from -20 (highest priority) to 19 (lowest priority)
+    #[serde(default = "default_niceness")]
+    pub niceness: i32,
+    // best-effort io priority, from 0 (highest) to 7 (lowest)
+    #[serde(default = "default_io_priority")]
+    pub ioPriority: u8,
+    // maximum virtual memory in megabytes (RLIMIT_AS), trin aborts when it's exceeded
+    #[serde(default)]
+    pub addressSpaceLimit: Option<u64>,
+    // cgroup v2 limits, only applied if the app's cgroup is delegated to the user.
+    // the cpu quota is a percentage of a single core, the memory max is in megabytes
+    #[serde(default)]
+    pub cgroupCpuQuota: Option<u32>,
+    #[serde(default)]
+    pub cgroupMemoryMax: Option<u64>,
+    #[serde(default = "default_subnetworks")]
+    pub subnetworks: Vec<Subnetwork>,
+    // udp port that trin binds to for discv5
+    #[serde(default = "default_discovery_port")]
+    pub discoveryPort: u16,
+    // the ip:port that is advertised to other nodes, eg. when trin is behind a nat
+    #[serde(default)]
+    pub externalAddress: Option<String>,
+    #[serde(default)]
+    pub bootnodes: Bootnodes,
+    // trin picks a dir in the platform's data dir when this isn't set
+    #[serde(default)]
+    pub dataDir: Option<String>,
+    #[serde(default)]
+    pub network: Network,
+    // a tracing filter for trin's logs, eg. "info,trin_history=debug". a level above info
+    // (eg. "warn") also silences the lines that the stats & readiness fallbacks parse
+    #[serde(default)]
+    pub logFilter: Option<String>,
+}
+
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+fn default_metrics_port() -> u16 {
+    9100
+}
+
+// run trin at a lower priority than interactive applications
+fn default_niceness() -> i32 {
+    10
+}
+
+fn default_io_priority() -> u8 {
+    7
+}
+
+fn default_subnetworks() -> Vec<Subnetwork> {
+    vec![Subnetwork::History, Subnetwork::State, Subnetwork::Beacon]
+}
+
+fn default_discovery_port() -> u16 {
+    DEFAULT_DISCOVERY_PORT
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Subnetwork {
+    State,
+    History,
+    Beacon,
+}
+
+impl Subnetwork {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::State => "state",
+            Self::History => "history",
+            Self::Beacon => "beacon",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    // trin's test network, called angelfood
+    Testnet,
+}
+
+// the nodes that trin contacts first to join the network
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Bootnodes {
+    // the bootnodes that are built into trin
+    #[default]
+    Default,
+    // don't bootstrap, only useful for a local test network
+    None,
+    Custom {
+        enrs: Vec<String>,
+    },
+}
+
+// a config field that trin would reject, or that doesn't make sense
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigError {
+    pub field: &'static str,
+    pub code: ConfigErrorCode,
+    pub message: String,
+}
+
+// lets the frontend react to an error without parsing its message
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigErrorCode {
+    InvalidPort,
+    PortClash,
+    OutOfRange,
+    InvalidFormat,
+    Missing,
+    Duplicate,
+    InsufficientDiskSpace,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+// joins the errors into a single message, for commands that return a plain error
+pub fn describe_errors(errors: &[ConfigError]) -> String {
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    errors.join("; ")
+}
+
+// the command line & environment that the trin sidecar is launched with
+#[derive(Debug, Default, PartialEq)]
+pub struct TrinArgs {
+    pub args: Vec<String>,
+    pub envs: Vec<(String, String)>,
+}
+
+impl TrinConfig {
+    // checks every field, returning all of the problems rather than just the first one.
+    // this doesn't look at the machine trin runs on, see `check_disk_space` for that
+    pub fn validate(&self) -> Vec<ConfigError> {
+        use ConfigErrorCode::*;
+        let mut errors = Vec::new();
+        let mut error = |field, code, message: String| {
+            errors.push(ConfigError {
+                field,
+                code,
+                message,
+            })
+        };
+
+        if !(1..=u16::MAX as usize).contains(&self.httpPort) {
+            let message = format!("{} is not a valid port", self.httpPort);
+            error("httpPort", InvalidPort, message);
+        }
+        if self.metricsPort == 0 {
+            error(
+                "metricsPort",
+                InvalidPort,
+                "0 is not a valid port".to_string(),
+            );
+        } else if self.metricsPort as usize == self.httpPort {
+            let message = "the metrics port must differ from the http port".to_string();
+            error("metricsPort", PortClash, message);
+        }
+        if self.discoveryPort == 0 {
+            error(
+                "discoveryPort",
+                InvalidPort,
+                "0 is not a valid port".to_string(),
+            );
+        }
+        if self.storage < MIN_STORAGE_MB {
+            let message = format!("trin needs at least {MIN_STORAGE_MB} MB of storage");
+            error("storage", OutOfRange, message);
+        }
+        if let Some(root) = self.trusted_block_root() {
+            let hex = root.strip_prefix("0x").unwrap_or_default();
+            if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                let message = "expected a 32-byte hex string starting with 0x".to_string();
+                error("trustedBlockRoot", InvalidFormat, message);
+            }
+        }
+        if self.subnetworks.is_empty() {
+            let message = "at least one subnetwork is required".to_string();
+            error("subnetworks", Missing, message);
+        }
+        let unique: HashSet<_> = self.subnetworks.iter().collect();
+        if unique.len() != self.subnetworks.len() {
+            let message = "subnetworks are listed twice".to_string();
+            error("subnetworks", Duplicate, message);
+        }
+        if let Some(address) = &self.externalAddress {
+            if address.parse::<SocketAddr>().is_err() {
+                let message = format!("{address:?} isn't an ip:port address");
+                error("externalAddress", InvalidFormat, message);
+            }
+        }
+        if let Bootnodes::Custom { enrs } = &self.bootnodes {
+            if enrs.is_empty() {
+                let message = "no custom bootnodes given".to_string();
+                error("bootnodes", Missing, message);
+            }
+            for enr in enrs.iter().filter(|enr| !is_enr(enr)) {
+                error(
+                    "bootnodes",
+                    InvalidFormat,
+                    format!("{enr:?} isn't a valid enr"),
+                );
+            }
+        }
+        if let Some(dir) = &self.dataDir {
+            let path = Path::new(dir);
+            if !path.is_absolute() {
+                let message = format!("{dir:?} isn't an absolute path");
+                error("dataDir", InvalidFormat, message);
+            } else if path.exists() && !path.is_dir() {
+                error(
+                    "dataDir",
+                    InvalidFormat,
+                    format!("{dir:?} isn't a directory"),
+                );
+            }
+        }
+        if let Some(filter) = &self.logFilter {
+            if let Err(e) = check_log_filter(filter) {
+                error("logFilter", InvalidFormat, e);
+            }
+        }
+        if !(-20..=19).contains(&self.niceness) {
+            let message = "expected a value from -20 to 19".to_string();
+            error("niceness", OutOfRange, message);
+        }
+        if self.ioPriority > 7 {
+            let message = "expected a value from 0 to 7".to_string();
+            error("ioPriority", OutOfRange, message);
+        }
+        if self
+            .addressSpaceLimit
+            .is_some_and(|limit| limit < MIN_ADDRESS_SPACE_MB)
+        {
+            let message = format!("trin needs at least {MIN_ADDRESS_SPACE_MB} MB of address space");
+            error("addressSpaceLimit", OutOfRange, message);
+        }
+        // a quota of 0 isn't a valid cpu.max value
+        if self.cgroupCpuQuota == Some(0) {
+            let message = "the cpu quota must be at least 1%".to_string();
+            error("cgroupCpuQuota", OutOfRange, message);
+        }
+        if self
+            .cgroupMemoryMax
+            .is_some_and(|max| max < MIN_CGROUP_MEMORY_MB)
+        {
+            let message = format!("trin needs at least {MIN_CGROUP_MEMORY_MB} MB of memory");
+            error("cgroupMemoryMax", OutOfRange, message);
+        }
+        errors
+    }
+
+    // trin grows its database up to the configured storage, so that much has to fit on
+    // the disk. the data trin already stored counts towards it. `available` is None if
+    // the free space of the disk couldn't be determined
+    pub fn check_disk_space(&self, available: Option<u64>, used: u64) -> Option<ConfigError> {
+        let available = available?;
+        let needed = (self.storage as u64 * BYTES_PER_MB).saturating_sub(used);
+        (needed > available).then(|| ConfigError {
+            field: "storage",
+            code: ConfigErrorCode::InsufficientDiskSpace,
+            message: format!(
+                "{} MB more are needed, but only {} MB are free",
+                needed / BYTES_PER_MB,
+                available / BYTES_PER_MB
+            ),
+        })
+    }
+
+    // the single place where the config is turned into trin's command line
+    pub fn trin_args(&self) -> Result<TrinArgs, String> {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            return Err(describe_errors(&errors));
+        }
+
+        let subnetworks: Vec<&str> = self.subnetworks.iter().map(Subnetwork::as_str).collect();
+        let network = match self.network {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "angelfood",
+        };
+        let mut args = vec![
+            "--web3-transport=http".to_string(),
+            format!("--web3-http-address=http://127.0.0.1:{}", self.httpPort),
+            format!("--portal-subnetworks={}", subnetworks.join(",")),
+            format!("--network={network}"),
+            format!("--mb={}", self.storage),
+            format!("--discovery-port={}", self.discoveryPort),
+            format!("--enable-metrics-with-url=127.0.0.1:{}", self.metricsPort),
+        ];
+        if let Some(root) = self.trusted_block_root() {
+            args.push(format!("--trusted-block-root={root}"));
+        }
+        if let Some(address) = &self.externalAddress {
+            args.push(format!("--external-address={address}"));
+        }
+        match &self.bootnodes {
+            Bootnodes::Default => {}
+            Bootnodes::None => args.push("--bootnodes=none".to_string()),
+            Bootnodes::Custom { enrs } => args.push(format!("--bootnodes={}", enrs.join(","))),
+        }
+        if let Some(dir) = &self.dataDir {
+            args.push(format!("--data-dir={dir}"));
+        }
+
+        // trin reads its log filter from the environment, there's no flag for it
+        let envs = self
+            .logFilter
+            .iter()
+            .map(|filter| ("RUST_LOG".to_string(), filter.clone()))
+            .collect();
+        Ok(TrinArgs { args, envs })
+    }
+
+    // "0x" is what the frontend stores until a trusted block root has been picked
+    fn trusted_block_root(&self) -> Option<&str> {
+        let root = self.trustedBlockRoot.trim();
+        (!root.is_empty() && root != "0x").then_some(root)
+    }
+}
+
+// enrs are "enr:" followed by a base64url encoded record
+fn is_enr(enr: &str) -> bool {
+    enr.strip_prefix("enr:").is_some_and(|record| {
+        !record.is_empty()
+            && record
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+// a comma separated list of "level" or "target=level" directives
+fn check_log_filter(filter: &str) -> Result<(), String> {
+    const LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+    for directive in filter.split(',').map(str::trim) {
+        let (target, level) = match directive.split_once('=') {
+            Some((target, level)) => (Some(target), level),
+            None if LEVELS.contains(&directive.to_lowercase().as_str()) => (None, directive),
+            // a bare target enables every level for it
+            None => (Some(directive), "trace"),
+        };
+        if let Some(target) = target {
+            let valid = !target.is_empty()
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':' || c == '-');
+            if !valid {
+                return Err(format!("{target:?} isn't a valid log target"));
+            }
+        }
+        if !LEVELS.contains(&level.to_lowercase().as_str()) {
+            return Err(format!("{level:?} isn't a log level"));
+        }
+    }
+    Ok(())
+}
+
+// what to do with a trin process left behind by a previous run of the app,
+// read from the "orphanedNodePolicy" key of the frontend's config store
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrphanPolicy {
+    // reattach monitoring to the running process
+    #[default]
+    Adopt,
+    // shut it down, so that it can be launched from scratch
+    Terminate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn config(json: serde_json::Value) -> TrinConfig {
+        let mut config = serde_json::json!({
+            "httpPort": 8545,
+            "storage": 2000,
+            "trustedBlockRoot": "0x",
+        });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(json.as_object().unwrap().clone());
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn test_default_args() {
+        let args = config(serde_json::json!({})).trin_args().unwrap();
+        assert_eq!(
+            args.args,
+            vec![
+                "--web3-transport=http",
+                "--web3-http-address=http://127.0.0.1:8545",
+                "--portal-subnetworks=history,state,beacon",
+                "--network=mainnet",
+                "--mb=2000",
+                "--discovery-port=9009",
+                "--enable-metrics-with-url=127.0.0.1:9100",
+            ]
+        );
+        assert!(args.envs.is_empty());
+    }
+
+    #[test]
+    fn test_all_args() {
+        let root = format!("0x{}", "ab".repeat(32));
+        let args = config(serde_json::json!({
+            "trustedBlockRoot": root,
+            "subnetworks": ["history", "beacon"],
+            "discoveryPort": 9010,
+            "externalAddress": "203.0.113.7:9010",
+            "bootnodes": { "kind": "custom", "enrs": ["enr:-IS4QHCYrYZbAKW", "enr:-IS4QJ2d11eu6dC7"] },
+            "dataDir": "/var/lib/trin",
+            "network": "testnet",
+            "logFilter": "info,trin_history=debug",
+        }))
+        .trin_args()
+        .unwrap();
+        assert_eq!(
+            args.args,
+            vec![
+                "--web3-transport=http".to_string(),
+                "--web3-http-address=http://127.0.0.1:8545".to_string(),
+                "--portal-subnetworks=history,beacon".to_string(),
+                "--network=angelfood".to_string(),
+                "--mb=2000".to_string(),
+                "--discovery-port=9010".to_string(),
+                "--enable-metrics-with-url=127.0.0.1:9100".to_string(),
+                format!("--trusted-block-root={root}"),
+                "--external-address=203.0.113.7:9010".to_string(),
+                "--bootnodes=enr:-IS4QHCYrYZbAKW,enr:-IS4QJ2d11eu6dC7".to_string(),
+                "--data-dir=/var/lib/trin".to_string(),
+            ]
+        );
+        assert_eq!(
+            args.envs,
+            vec![(
+                "RUST_LOG".to_string(),
+                "info,trin_history=debug".to_string()
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case(serde_json::json!({ "httpPort": 70000 }), "httpPort")]
+    #[case(serde_json::json!({ "metricsPort": 8545 }), "metricsPort")]
+    #[case(serde_json::json!({ "discoveryPort": 0 }), "discoveryPort")]
+    #[case(serde_json::json!({ "storage": 99 }), "storage")]
+    #[case(serde_json::json!({ "trustedBlockRoot": "0x1234" }), "trustedBlockRoot")]
+    #[case(serde_json::json!({ "subnetworks": [] }), "subnetworks")]
+    #[case(serde_json::json!({ "subnetworks": ["state", "state"] }), "subnetworks")]
+    #[case(serde_json::json!({ "externalAddress": "localhost" }), "externalAddress")]
+    #[case(serde_json::json!({ "bootnodes": { "kind": "custom", "enrs": [] } }), "bootnodes")]
+    #[case(serde_json::json!({ "bootnodes": { "kind": "custom", "enrs": ["enode://abc"] } }), "bootnodes")]
+    #[case(serde_json::json!({ "dataDir": "relative/dir" }), "dataDir")]
+    #[case(serde_json::json!({ "logFilter": "trin_history=loud" }), "logFilter")]
+    #[case(serde_json::json!({ "logFilter": "info,=debug" }), "logFilter")]
+    #[case(serde_json::json!({ "niceness": 20 }), "niceness")]
+    #[case(serde_json::json!({ "ioPriority": 8 }), "ioPriority")]
+    #[case(serde_json::json!({ "addressSpaceLimit": 512 }), "addressSpaceLimit")]
+    #[case(serde_json::json!({ "cgroupCpuQuota": 0 }), "cgroupCpuQuota")]
+    #[case(serde_json::json!({ "cgroupMemoryMax": 64 }), "cgroupMemoryMax")]
+    fn test_invalid_config(#[case] json: serde_json::Value, #[case] field: &str) {
+        let config = config(json);
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(errors[0].field, field);
+        assert!(config.trin_args().unwrap_err().starts_with(field));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let config = config(serde_json::json!({ "trustedBlockRoot": "0xzz", "metricsPort": 8545 }));
+        let codes: Vec<_> = config
+            .validate()
+            .iter()
+            .map(|error| (error.field, error.code))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("metricsPort", ConfigErrorCode::PortClash),
+                ("trustedBlockRoot", ConfigErrorCode::InvalidFormat),
+            ]
+        );
+    }
+
+    #[rstest]
+    // 2000 MB are configured
+    #[case(Some(3_000_000_000), 0, false)]
+    #[case(Some(1_000_000_000), 0, true)]
+    // the data that's already stored doesn't need more space
+    #[case(Some(1_000_000_000), 1_500_000_000, false)]
+    #[case(None, 0, false)]
+    fn test_check_disk_space(
+        #[case] available: Option<u64>,
+        #[case] used: u64,
+        #[case] insufficient: bool,
+    ) {
+        let error = config(serde_json::json!({})).check_disk_space(available, used);
+        assert_eq!(error.is_some(), insufficient);
+    }
 }
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri_plugin_shell::process::TerminatedPayload;
+
+const CRASH_REPORT_FILE: &str = "crash_report.json";
+
+// the number of stdout/stderr lines from trin that are kept for crash reports
+const OUTPUT_BUFFER_LINES: usize = 300;
+
+// the most recent lines that trin wrote to stdout & stderr
+#[derive(Debug, Default)]
+pub struct OutputBuffer {
+    lines: VecDeque<String>,
+}
+
+impl OutputBuffer {
+    pub fn push(&mut self, line: &str) {
+        if self.lines.len() == OUTPUT_BUFFER_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.trim_end().to_string());
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+// everything we know about why trin died, this is saved to the app data dir
+// so that it's still available after the app is restarted
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    // unix timestamp in seconds
+    pub timestamp: u64,
+    pub reason: String,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub output: Vec<String>,
+}
+
+impl CrashReport {
+    pub fn new(reason: String, exit: Option<&TerminatedPayload>, output: &OutputBuffer) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            timestamp,
+            reason,
+            exit_code: exit.and_then(|exit| exit.code),
+            signal: exit.and_then(|exit| exit.signal),
+            output: output.lines.iter().cloned().collect(),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let report = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(CRASH_REPORT_FILE), report).map_err(|e| e.to_string())
+    }
+
+    pub fn load(dir: &Path) -> Result<Option<Self>, String> {
+        let path = dir.join(CRASH_REPORT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let report = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&report).map_err(|e| e.to_string())
+    }
+}
+
+pub fn describe_exit(exit: &TerminatedPayload) -> String {
+    match (exit.code, exit.signal) {
+        (Some(code), _) => format!("trin exited with code {code}"),
+        (None, Some(signal)) => format!("trin was killed by signal {signal}"),
+        (None, None) => "trin exited".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_buffer_keeps_latest_lines() {
+        let mut output = OutputBuffer::default();
+        for i in 0..OUTPUT_BUFFER_LINES + 10 {
+            output.push(&format!("line {i}\n"));
+        }
+        let report = CrashReport::new("test".to_string(), None, &output);
+        assert_eq!(report.output.len(), OUTPUT_BUFFER_LINES);
+        assert_eq!(report.output[0], "line 10");
+        assert_eq!(
+            report.output.last().unwrap(),
+            &format!("line {}", OUTPUT_BUFFER_LINES + 9)
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_crash_report() {
+        let dir = std::env::temp_dir().join("trin-desktop-crash-report-test");
+        let mut output = OutputBuffer::default();
+        output.push("thread 'main' panicked at src/main.rs:1:1");
+        let exit = TerminatedPayload {
+            code: Some(101),
+            signal: None,
+        };
+        let report = CrashReport::new(describe_exit(&exit), Some(&exit), &output);
+        report.save(&dir).unwrap();
+
+        let loaded = CrashReport::load(&dir).unwrap().unwrap();
+        assert_eq!(loaded.reason, "trin exited with code 101");
+        assert_eq!(loaded.exit_code, Some(101));
+        assert_eq!(loaded.signal, None);
+        assert_eq!(loaded.output, report.output);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
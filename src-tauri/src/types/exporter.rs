@@ -0,0 +1,336 @@
+use crate::types::activity::SubnetworkActivity;
+use crate::types::node::{NodeStats, SubnetworkDataLog};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// every metric served by the exporter starts with this prefix, so that they
+// can't be confused with the metrics trin serves itself
+const PREFIX: &str = "trin_desktop";
+
+// the local metrics exporter is opt-in, it's stored under the "metricsExporter"
+// key of the frontend's config store
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExporterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_exporter_port")]
+    pub port: u16,
+}
+
+impl Default for ExporterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_exporter_port(),
+        }
+    }
+}
+
+// one above the default port of trin's own metrics
+fn default_exporter_port() -> u16 {
+    9101
+}
+
+// the name, help text & value of a metric that is reported per subnetwork
+type SubnetworkMetric<T, S = SubnetworkDataLog> = (&'static str, &'static str, fn(&S) -> T);
+
+// what the crash supervisor knows about the node, next to its stats
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SupervisorMetrics {
+    pub running: bool,
+    pub uptime_secs: u64,
+    // since the app was started
+    pub restarts: u64,
+    pub crashes: u64,
+    // crashes inside the supervisor's crash loop window
+    pub recent_crashes: usize,
+}
+
+// renders the node stats & supervisor metrics in the OpenMetrics text format
+pub fn render(stats: &NodeStats, supervisor: &SupervisorMetrics) -> String {
+    let mut metrics = OpenMetricsWriter::default();
+
+    metrics.family("up", "gauge", "Whether the trin node is running");
+    metrics.sample("up", &[], supervisor.running as u8 as f64);
+    metrics.family(
+        "uptime_seconds",
+        "gauge",
+        "Seconds since the trin node was last started",
+    );
+    metrics.sample("uptime_seconds", &[], supervisor.uptime_secs as f64);
+    metrics.family(
+        "restarts",
+        "counter",
+        "Restarts of the trin node by the crash supervisor",
+    );
+    metrics.sample("restarts_total", &[], supervisor.restarts as f64);
+    metrics.family("crashes", "counter", "Crashes of the trin node");
+    metrics.sample("crashes_total", &[], supervisor.crashes as f64);
+    metrics.family(
+        "recent_crashes",
+        "gauge",
+        "Crashes of the trin node inside the crash loop window",
+    );
+    metrics.sample("recent_crashes", &[], supervisor.recent_crashes as f64);
+
+    metrics.family(
+        "cpu_usage_percent",
+        "gauge",
+        "CPU usage of the trin process tree",
+    );
+    metrics.sample("cpu_usage_percent", &[], stats.cpu as f64);
+    metrics.family(
+        "memory_bytes",
+        "gauge",
+        "Resident memory of the trin process tree",
+    );
+    metrics.sample("memory_bytes", &[], stats.memory as f64);
+    metrics.family(
+        "virtual_memory_bytes",
+        "gauge",
+        "Virtual memory of the trin process tree",
+    );
+    metrics.sample("virtual_memory_bytes", &[], stats.virtual_memory as f64);
+    if let Some(threads) = stats.threads {
+        metrics.family("threads", "gauge", "Threads of the trin process tree");
+        metrics.sample("threads", &[], threads as f64);
+    }
+    if let Some(open_fds) = stats.open_fds {
+        metrics.family(
+            "open_fds",
+            "gauge",
+            "Open file descriptors of the trin process tree",
+        );
+        metrics.sample("open_fds", &[], open_fds as f64);
+    }
+    metrics.family(
+        "disk_read_bytes_per_second",
+        "gauge",
+        "Disk reads of the trin process tree",
+    );
+    metrics.sample(
+        "disk_read_bytes_per_second",
+        &[],
+        stats.disk_read_rate as f64,
+    );
+    metrics.family(
+        "disk_write_bytes_per_second",
+        "gauge",
+        "Disk writes of the trin process tree",
+    );
+    metrics.sample(
+        "disk_write_bytes_per_second",
+        &[],
+        stats.disk_write_rate as f64,
+    );
+
+    let storage = &stats.storage;
+    if let Some(disk_usage) = storage.disk_usage {
+        metrics.family(
+            "storage_disk_usage_bytes",
+            "gauge",
+            "Size of the data dir of trin",
+        );
+        metrics.sample("storage_disk_usage_bytes", &[], disk_usage as f64);
+    }
+    metrics.family(
+        "storage_budget_bytes",
+        "gauge",
+        "Storage configured for trin",
+    );
+    metrics.sample("storage_budget_bytes", &[], storage.budget as f64);
+    if let Some(budget_used) = storage.budget_used {
+        metrics.family(
+            "storage_budget_used_percent",
+            "gauge",
+            "Percentage of the configured storage that is used",
+        );
+        metrics.sample("storage_budget_used_percent", &[], budget_used);
+    }
+    if let Some(time_to_full) = storage.time_to_full_secs {
+        metrics.family(
+            "storage_time_to_full_seconds",
+            "gauge",
+            "Projected time until the configured storage is used up",
+        );
+        metrics.sample("storage_time_to_full_seconds", &[], time_to_full as f64);
+    }
+
+    let subnetworks = [
+        ("state", &stats.state_data, &stats.state_activity),
+        ("history", &stats.history_data, &stats.history_activity),
+        ("beacon", &stats.beacon_data, &stats.beacon_activity),
+    ];
+    let gauges: [SubnetworkMetric<f64>; 4] = [
+        ("radius_percent", "Radius of the subnetwork", |data| {
+            data.radius as f64
+        }),
+        (
+            "content_megabytes",
+            "Size of the content stored for the subnetwork",
+            |data| data.content_current as f64,
+        ),
+        (
+            "content_capacity_megabytes",
+            "Storage capacity of the subnetwork",
+            |data| data.content_total as f64,
+        ),
+        (
+            "content_items",
+            "Content items stored for the subnetwork",
+            |data| data.count as f64,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        metrics.family(name, "gauge", help);
+        for (subnetwork, data, _) in subnetworks {
+            metrics.sample(name, &[("subnetwork", subnetwork)], value(data));
+        }
+    }
+    // the lifetime totals are used, since trin's own counters reset when it restarts
+    let counters: [SubnetworkMetric<[u64; 2], SubnetworkActivity>; 3] = [
+        ("offers", "Offers handled by the subnetwork", |activity| {
+            [activity.totals.offers_in, activity.totals.offers_out]
+        }),
+        ("accepts", "Accepts handled by the subnetwork", |activity| {
+            [activity.totals.accepts_in, activity.totals.accepts_out]
+        }),
+        (
+            "validations",
+            "Validations handled by the subnetwork",
+            |activity| {
+                [
+                    activity.totals.validations_in,
+                    activity.totals.validations_out,
+                ]
+            },
+        ),
+    ];
+    for (name, help, value) in counters {
+        metrics.family(name, "counter", help);
+        let sample_name = format!("{name}_total");
+        for (subnetwork, _, activity) in subnetworks {
+            let [inbound, outbound] = value(activity);
+            for (direction, value) in [("in", inbound), ("out", outbound)] {
+                let labels = [("subnetwork", subnetwork), ("direction", direction)];
+                metrics.sample(&sample_name, &labels, value as f64);
+            }
+        }
+    }
+
+    metrics.family(
+        "latest_finalized_block",
+        "gauge",
+        "Latest finalized beacon block seen by the node",
+    );
+    metrics.sample(
+        "latest_finalized_block",
+        &[],
+        stats.latest_finalized_block as f64,
+    );
+    metrics.family(
+        "latest_optimistic_block",
+        "gauge",
+        "Latest optimistic beacon block seen by the node",
+    );
+    metrics.sample(
+        "latest_optimistic_block",
+        &[],
+        stats.latest_optimistic_block as f64,
+    );
+    if let Some(peers) = stats.network.connected_peers {
+        metrics.family("connected_peers", "gauge", "Peers connected to the node");
+        metrics.sample("connected_peers", &[], peers as f64);
+    }
+    metrics.family(
+        "log_parse_failures",
+        "counter",
+        "Log lines of trin that couldn't be parsed",
+    );
+    metrics.sample(
+        "log_parse_failures_total",
+        &[],
+        stats.log_parse_failures as f64,
+    );
+
+    metrics.finish()
+}
+
+#[derive(Default)]
+struct OpenMetricsWriter {
+    text: String,
+}
+
+impl OpenMetricsWriter {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# TYPE {PREFIX}_{name} {kind}");
+        let _ = writeln!(self.text, "# HELP {PREFIX}_{name} {help}.");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.text, "{PREFIX}_{name}");
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{value}\""))
+                .collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {value}");
+    }
+
+    fn finish(mut self) -> String {
+        self.text.push_str("# EOF\n");
+        self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut stats = NodeStats {
+            cpu: 12.5,
+            memory: 1024,
+            threads: Some(8),
+            latest_finalized_block: 42,
+            ..Default::default()
+        };
+        stats.history_data.radius = 8.5;
+        stats.history_activity.totals.offers_in = 26700;
+        let supervisor = SupervisorMetrics {
+            running: true,
+            uptime_secs: 60,
+            restarts: 2,
+            crashes: 3,
+            recent_crashes: 1,
+        };
+        let text = render(&stats, &supervisor);
+
+        for line in [
+            "# TYPE trin_desktop_up gauge",
+            "trin_desktop_up 1",
+            "trin_desktop_uptime_seconds 60",
+            "# TYPE trin_desktop_restarts counter",
+            "trin_desktop_restarts_total 2",
+            "trin_desktop_crashes_total 3",
+            "trin_desktop_cpu_usage_percent 12.5",
+            "trin_desktop_memory_bytes 1024",
+            "trin_desktop_threads 8",
+            "trin_desktop_radius_percent{subnetwork=\"history\"} 8.5",
+            "trin_desktop_offers_total{subnetwork=\"history\",direction=\"in\"} 26700",
+            "trin_desktop_latest_finalized_block 42",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line:?}");
+        }
+        // unknown values are left out, rather than reported as zero
+        assert!(!text.contains("trin_desktop_open_fds"));
+        assert!(!text.contains("trin_desktop_connected_peers"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
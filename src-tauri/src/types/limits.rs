@@ -0,0 +1,23 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+// the resource limits that were actually applied to the trin process, any limit
+// that was configured but couldn't be applied is listed in `errors` instead
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedLimits {
+    pub pid: u32,
+    pub niceness: Option<i32>,
+    // best-effort io priority, from 0 (highest) to 7 (lowest)
+    pub io_priority: Option<u8>,
+    // megabytes
+    pub address_space_limit: Option<u64>,
+    // percentage of a single cpu core
+    pub cgroup_cpu_quota: Option<u32>,
+    // megabytes
+    pub cgroup_memory_max: Option<u64>,
+    // the cgroup trin was moved into, it is removed once trin exits
+    #[serde(skip)]
+    pub cgroup: Option<PathBuf>,
+    pub errors: Vec<String>,
+}
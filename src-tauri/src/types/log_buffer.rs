@@ -0,0 +1,354 @@
+use crate::types::log_parser::LogLine;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+// the number of trin log records kept for the log viewer
+const LOG_BUFFER_RECORDS: usize = 10_000;
+
+// the most records returned by a single "get_trin_logs" call
+const MAX_PAGE_SIZE: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level.to_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => log::Level::Trace,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Error => log::Level::Error,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+// a single line of trin output. lines that aren't tracing records, eg. a panic
+// message, are kept as they are, without a timestamp, level or target
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+    // increases with every record, used as the cursor for paging
+    pub id: u64,
+    pub stream: LogStream,
+    pub timestamp: Option<String>,
+    pub level: Option<LogLevel>,
+    pub target: Option<String>,
+    pub message: String,
+}
+
+impl LogRecord {
+    fn new(id: u64, stream: LogStream, line: &str, log_line: Option<&LogLine>) -> Self {
+        let Some(log_line) = log_line else {
+            return Self {
+                id,
+                stream,
+                timestamp: None,
+                level: None,
+                target: None,
+                message: line.trim_end().to_string(),
+            };
+        };
+        // structured fields are appended to the message, like tracing's text format does
+        let mut message = log_line.message().to_string();
+        for (name, value) in log_line
+            .fields
+            .iter()
+            .filter(|(name, _)| *name != "message")
+        {
+            match value {
+                // without the quotes
+                Value::String(value) => message.push_str(&format!(" {name}={value}")),
+                value => message.push_str(&format!(" {name}={value}")),
+            }
+        }
+        Self {
+            id,
+            stream,
+            timestamp: log_line.timestamp.clone(),
+            level: LogLevel::parse(&log_line.level),
+            target: Some(log_line.target.clone()),
+            message,
+        }
+    }
+}
+
+// the parameters of the "get_trin_logs" command, every filter is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogQuery {
+    // only records at this level or above, records without a level always match
+    pub min_level: Option<LogLevel>,
+    // only records of this target or its submodules, eg. "trin_history"
+    pub target: Option<String>,
+    // a regex that the message has to match
+    pub search: Option<String>,
+    // only records older than this id, for paging back from the newest records
+    pub before: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    // oldest first
+    pub records: Vec<LogRecord>,
+    // pass as `before` to get the previous page, None if there are no older records
+    pub next_before: Option<u64>,
+}
+
+// the most recent log records of trin, for the in-app log viewer
+#[derive(Debug, Default)]
+pub struct LogBuffer {
+    records: VecDeque<LogRecord>,
+    next_id: u64,
+}
+
+impl LogBuffer {
+    pub fn push(&mut self, stream: LogStream, line: &str, log_line: Option<&LogLine>) -> LogRecord {
+        if self.records.len() == LOG_BUFFER_RECORDS {
+            self.records.pop_front();
+        }
+        let record = LogRecord::new(self.next_id, stream, line, log_line);
+        self.next_id += 1;
+        self.records.push_back(record.clone());
+        record
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    // the newest records that match the query, returned oldest first
+    pub fn query(&self, query: &LogQuery) -> Result<LogPage, String> {
+        let search = match &query.search {
+            Some(search) => Some(Regex::new(search).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        let limit = query.limit.unwrap_or(100).min(MAX_PAGE_SIZE);
+        let mut matches = self
+            .records
+            .iter()
+            .rev()
+            .filter(|record| query.before.is_none_or(|before| record.id < before))
+            .filter(|record| match (query.min_level, record.level) {
+                (Some(min_level), Some(level)) => level >= min_level,
+                _ => true,
+            })
+            .filter(|record| match (&query.target, &record.target) {
+                (Some(target), Some(record_target)) => {
+                    record_target == target || record_target.starts_with(&format!("{target}::"))
+                }
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .filter(|record| {
+                search
+                    .as_ref()
+                    .is_none_or(|search| search.is_match(&record.message))
+            });
+
+        let mut records: Vec<LogRecord> = matches.by_ref().take(limit).cloned().collect();
+        let next_before = match matches.next() {
+            Some(_) => records.last().map(|record| record.id),
+            None => None,
+        };
+        records.reverse();
+        Ok(LogPage {
+            records,
+            next_before,
+        })
+    }
+
+    // every record in the buffer, formatted like trin's own text output
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for record in &self.records {
+            if record.stream == LogStream::Stderr {
+                text.push_str("[stderr] ");
+            }
+            if let Some(timestamp) = &record.timestamp {
+                text.push_str(&format!("{timestamp} "));
+            }
+            if let Some(level) = record.level {
+                text.push_str(&format!("{level:?} ").to_uppercase());
+            }
+            if let Some(target) = &record.target {
+                text.push_str(&format!("{target}: "));
+            }
+            text.push_str(&record.message);
+            text.push('\n');
+        }
+        text
+    }
+
+    // the distinct targets in the buffer, for the target filter
+    pub fn targets(&self) -> Vec<String> {
+        let mut targets: Vec<String> = self
+            .records
+            .iter()
+            .filter_map(|record| record.target.clone())
+            .collect();
+        targets.sort();
+        targets.dedup();
+        targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer() -> LogBuffer {
+        let mut buffer = LogBuffer::default();
+        let lines = [
+            (
+                LogStream::Stdout,
+                "2024-10-31T19:13:41Z  INFO trin: Launching Trin",
+            ),
+            (
+                LogStream::Stdout,
+                "2024-10-31T19:13:42Z DEBUG portalnet::overlay: sent ping",
+            ),
+            (
+                LogStream::Stdout,
+                "2024-10-31T19:13:43Z  WARN trin_history::storage: disk almost full",
+            ),
+            (
+                LogStream::Stdout,
+                "2024-10-31T19:13:44Z  INFO trin_history: reports~ data: radius=15%",
+            ),
+            (
+                LogStream::Stderr,
+                "thread 'main' panicked at src/main.rs:1:1",
+            ),
+        ];
+        for (stream, line) in lines {
+            buffer.push(stream, line, LogLine::parse(line).as_ref());
+        }
+        buffer
+    }
+
+    fn messages(page: &LogPage) -> Vec<&str> {
+        page.records
+            .iter()
+            .map(|record| record.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_log_records() {
+        let buffer = buffer();
+        let page = buffer
+            .query(&LogQuery {
+                min_level: Some(LogLevel::Info),
+                target: Some("trin_history".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            messages(&page),
+            vec!["disk almost full", "reports~ data: radius=15%"]
+        );
+
+        let page = buffer
+            .query(&LogQuery {
+                search: Some(r"panicked|ping$".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            messages(&page),
+            vec!["sent ping", "thread 'main' panicked at src/main.rs:1:1"]
+        );
+        assert_eq!(page.records[1].level, None);
+        assert_eq!(page.records[1].stream, LogStream::Stderr);
+
+        assert!(buffer
+            .query(&LogQuery {
+                search: Some("(".to_string()),
+                ..Default::default()
+            })
+            .is_err());
+        assert_eq!(
+            buffer.targets(),
+            vec![
+                "portalnet::overlay",
+                "trin",
+                "trin_history",
+                "trin_history::storage"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_page_through_log_records() {
+        let buffer = buffer();
+        let query = LogQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = buffer.query(&query).unwrap();
+        assert_eq!(
+            page.records
+                .iter()
+                .map(|record| record.id)
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert_eq!(page.next_before, Some(3));
+
+        let page = buffer
+            .query(&LogQuery {
+                before: page.next_before,
+                ..query
+            })
+            .unwrap();
+        assert_eq!(
+            page.records
+                .iter()
+                .map(|record| record.id)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let page = buffer
+            .query(&LogQuery {
+                before: Some(1),
+                limit: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(page.records.len(), 1);
+        assert_eq!(page.next_before, None);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::types::node::{NodeStats, SubnetworkDataLog};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// matches a line in trin's default text log format, eg.
+// "2024-10-31T19:13:41.425824Z  INFO trin_history: reports~ data: ..."
+static TEXT_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\S+)\s+(TRACE|DEBUG|INFO|WARN|ERROR)\s+([\w:]+):\s(.*)")
+        .expect("invalid text log line regex")
+});
+
+// the color codes that tracing adds when it thinks it's writing to a terminal
+static ANSI_ESCAPE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m").expect("invalid ansi escape regex"));
+
+// a single log line from trin. the sidecar writes tracing's human-formatted text output,
+// trin has no option to switch it to json, so that is what's normally scraped. lines in
+// tracing's json format, eg. from a custom trin build, are detected and parsed as well
+#[derive(Debug, Deserialize)]
+pub struct LogLine {
+    pub timestamp: Option<String>,
+    pub level: String,
+    pub target: String,
+    // the message is in the "message" field, the rest are structured fields
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+impl LogLine {
+    // the format is detected for every line, so nothing has to be configured for either
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.starts_with('{') {
+            if let Ok(log_line) = serde_json::from_str(line) {
+                return Some(log_line);
+            }
+        }
+        let line = ANSI_ESCAPE_RE.replace_all(line, "");
+        let captures = TEXT_LINE_RE.captures(&line)?;
+        let mut fields = Map::new();
+        fields.insert("message".to_string(), captures[4].into());
+        Some(Self {
+            timestamp: Some(captures[1].to_string()),
+            level: captures[2].to_string(),
+            target: captures[3].to_string(),
+            fields,
+        })
+    }
+
+    pub fn message(&self) -> &str {
+        self.fields
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+}
+
+// a change to the node stats, parsed from a single log line
+#[derive(Debug)]
+pub enum LogUpdate {
+    State(SubnetworkDataLog),
+    History(SubnetworkDataLog),
+    Beacon(SubnetworkDataLog),
+}
+
+impl LogUpdate {
+    pub fn apply(self, stats: &mut NodeStats) {
+        match self {
+            Self::State(log) => stats.state_data = log,
+            Self::History(log) => stats.history_data = log,
+            Self::Beacon(log) => stats.beacon_data = log,
+        }
+    }
+}
+
+// parses the log lines of a single tracing target. supporting a new kind of log line
+// only requires implementing this trait and registering the parser in `LogParsers`
+pub trait LogParser: Send + Sync {
+    // the tracing target of the lines this parser handles, eg. "trin_history"
+    fn target(&self) -> &'static str;
+
+    // returns None if the line isn't one this parser is interested in
+    fn parse(&self, line: &LogLine) -> Option<Result<LogUpdate, String>>;
+}
+
+// the periodic "reports~ data:" summary that every subnetwork logs
+pub struct SubnetworkReportParser {
+    target: &'static str,
+    update: fn(SubnetworkDataLog) -> LogUpdate,
+}
+
+impl LogParser for SubnetworkReportParser {
+    fn target(&self) -> &'static str {
+        self.target
+    }
+
+    fn parse(&self, line: &LogLine) -> Option<Result<LogUpdate, String>> {
+        if !line.message().contains("reports~ data:") {
+            return None;
+        }
+        // prefer structured fields, which only json lines have, and fall back to
+        // scraping the formatted message
+        let log = match SubnetworkDataLog::deserialize(&line.fields) {
+            Ok(log) => Ok(log),
+            Err(_) => SubnetworkDataLog::parse_log_line(line.message()).map_err(|e| e.to_string()),
+        };
+        Some(log.map(self.update))
+    }
+}
+
+// all of the known log parsers, indexed by target
+pub struct LogParsers {
+    parsers: HashMap<&'static str, Vec<Box<dyn LogParser>>>,
+}
+
+impl LogParsers {
+    pub fn register(&mut self, parser: impl LogParser + 'static) {
+        self.parsers
+            .entry(parser.target())
+            .or_default()
+            .push(Box::new(parser));
+    }
+
+    // dispatches the line to the parsers of its target. lines from submodules,
+    // eg. "trin_history::storage", are also handled by the parsers of their crate
+    pub fn parse(&self, line: &LogLine) -> Option<Result<LogUpdate, String>> {
+        let target = line.target.as_str();
+        let krate = target.split("::").next().unwrap_or(target);
+        let mut targets = vec![target];
+        if krate != target {
+            targets.push(krate);
+        }
+        targets
+            .into_iter()
+            .filter_map(|target| self.parsers.get(target))
+            .flatten()
+            .find_map(|parser| parser.parse(line))
+    }
+}
+
+impl Default for LogParsers {
+    fn default() -> Self {
+        let mut parsers = Self {
+            parsers: HashMap::new(),
+        };
+        parsers.register(SubnetworkReportParser {
+            target: "trin_state",
+            update: LogUpdate::State,
+        });
+        parsers.register(SubnetworkReportParser {
+            target: "trin_history",
+            update: LogUpdate::History,
+        });
+        parsers.register(SubnetworkReportParser {
+            target: "trin_beacon",
+            update: LogUpdate::Beacon,
+        });
+        parsers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn parse(parsers: &LogParsers, line: &str) -> Option<Result<LogUpdate, String>> {
+        parsers.parse(&LogLine::parse(line)?)
+    }
+
+    #[test]
+    fn test_log_lines_are_dispatched_by_target() {
+        let parsers = LogParsers::default();
+        let line = "2024-10-31T19:13:41.425824Z  INFO trin_beacon: reports~ data: radius=15% content=116.7/120mb #=13763 disk=267.1mb; msgs: offers=0/0, accepts=0/0, validations=0/0";
+        let update = parse(&parsers, line).unwrap().unwrap();
+
+        let mut stats = NodeStats::default();
+        update.apply(&mut stats);
+        assert_eq!(stats.beacon_data.count, 13763);
+        assert_eq!(stats.history_data.count, 0);
+
+        let line =
+            "2024-10-31T19:13:41.425824Z  INFO trin_history::storage: reports~ data: radius=8.8%";
+        assert!(parse(&parsers, line).unwrap().is_err());
+
+        let line =
+            "2024-10-31T19:13:41.425824Z  INFO portalnet::discovery: reports~ data: radius=8.8%";
+        assert!(parse(&parsers, line).is_none());
+        let line = "2024-10-31T19:13:41.425824Z  WARN trin_state: failed to fetch content";
+        assert!(parse(&parsers, line).is_none());
+    }
+
+    #[rstest]
+    // the report is only available as a formatted message
+    #[case(r#"{"timestamp":"2024-10-31T19:13:41.425824Z","level":"INFO","fields":{"message":"reports~ data: radius=15% content=116.7/120mb #=13763 disk=267.1mb; msgs: offers=0/0, accepts=0/0, validations=0/0"},"target":"trin_history"}"#)]
+    // the report comes with structured fields
+    #[case(r#"{"timestamp":"2024-10-31T19:13:41.425824Z","level":"INFO","fields":{"message":"reports~ data:","radius":15.0,"content_current":116.7,"content_total":120.0,"count":13763,"disk_usage":267.1,"offers_in":0,"offers_out":0,"accepts_in":0,"accepts_out":0,"validations_in":0,"validations_out":0},"target":"trin_history"}"#)]
+    fn test_parse_json_log_lines(#[case] line: &str) {
+        let parsers = LogParsers::default();
+        let Some(Ok(LogUpdate::History(log))) = parse(&parsers, line) else {
+            panic!("failed to parse json log line");
+        };
+        assert_eq!(log.radius, 15.0);
+        assert_eq!(log.content_current, 116.7);
+        assert_eq!(log.count, 13763);
+    }
+
+    #[test]
+    fn test_parse_log_line_formats() {
+        let line = LogLine::parse(
+            r#"{"timestamp":"2024-10-31T19:13:41Z","level":"WARN","fields":{"message":"no peers"},"target":"portalnet::overlay"}"#,
+        )
+        .unwrap();
+        assert_eq!(line.level, "WARN");
+        assert_eq!(line.target, "portalnet::overlay");
+        assert_eq!(line.message(), "no peers");
+
+        let line =
+            LogLine::parse("2024-10-31T19:13:41Z  WARN portalnet::overlay: no peers").unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("2024-10-31T19:13:41Z"));
+        assert_eq!(line.level, "WARN");
+        assert_eq!(line.target, "portalnet::overlay");
+        assert_eq!(line.message(), "no peers");
+
+        let line = LogLine::parse(
+            "2024-10-31T19:13:41Z \x1b[33m WARN\x1b[0m \x1b[2mportalnet::overlay\x1b[0m: no peers",
+        )
+        .unwrap();
+        assert_eq!(line.level, "WARN");
+        assert_eq!(line.target, "portalnet::overlay");
+
+        assert!(LogLine::parse("Launching Trin: version 0.1.0").is_none());
+    }
+}
@@ -0,0 +1,342 @@
+use crate::types::node::{NodeStats, SubnetworkDataLog};
+use serde::{Deserialize, Serialize};
+
+// the names of the metrics exported by trin's `portalnet::metrics` module. metrics that
+// an older (or newer) trin binary doesn't export are simply left out of the stats
+const RADIUS_RATIO: &str = "trin_radius_ratio";
+const ENTRY_COUNT: &str = "trin_entry_count";
+const CONTENT_STORAGE_USAGE: &str = "trin_content_storage_usage_bytes";
+const TOTAL_STORAGE_USAGE: &str = "trin_total_storage_usage_bytes";
+const STORAGE_CAPACITY: &str = "trin_storage_capacity_bytes";
+const MESSAGES: &str = "trin_message_total";
+const VALIDATIONS: &str = "trin_validation_total";
+const CONNECTED_PEERS: &str = "trin_connected_peers";
+const UTP_PAYLOAD_BYTES: &str = "trin_utp_payload_bytes_total";
+const UTP_ACTIVE_STREAMS: &str = "trin_utp_active_streams";
+const LOOKUP_DURATION: &str = "trin_lookup_duration_seconds";
+
+// where the subnetwork data in the node stats comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatsSource {
+    #[default]
+    Logs,
+    Metrics,
+}
+
+// network activity that is only available from trin's metrics
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkMetrics {
+    pub connected_peers: Option<u64>,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub active_streams: u64,
+    // average duration of a content lookup since trin started
+    pub lookup_latency_ms: Option<f64>,
+}
+
+// a single sample from the prometheus text exposition format
+#[derive(Debug, PartialEq)]
+pub struct MetricSample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+impl MetricSample {
+    fn label(&self, name: &str) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// all samples from a single scrape of trin's metrics endpoint
+#[derive(Debug, Default)]
+pub struct Metrics {
+    samples: Vec<MetricSample>,
+}
+
+impl Metrics {
+    // parses the prometheus text exposition format, skipping any line it doesn't understand
+    pub fn parse(text: &str) -> Self {
+        let samples = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(parse_sample)
+            .collect();
+        Self { samples }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // the sum of all samples of the metric whose labels match the given ones
+    fn sum(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.name == name)
+            .filter(|sample| {
+                labels
+                    .iter()
+                    .all(|(label, value)| sample.label(label) == Some(*value))
+            })
+            .map(|sample| sample.value)
+            .reduce(|sum, value| sum + value)
+    }
+
+    // whether any sample of the metric has the given labels, a counter without the
+    // labels of eg. a message type is zero, while a missing metric isn't known at all
+    fn has(&self, name: &str, labels: &[(&str, &str)]) -> bool {
+        self.sum(name, labels).is_some()
+    }
+
+    // updates the data of a subnetwork with the metrics that were scraped, returns
+    // whether there were any for it. fields without a metric keep their value
+    fn apply_subnetwork(&self, protocol: &str, data: &mut SubnetworkDataLog) -> bool {
+        let protocol = ("protocol", protocol);
+        let value = |name, labels: &[(&str, &str)]| {
+            let mut labels = labels.to_vec();
+            labels.push(protocol);
+            self.sum(name, &labels)
+        };
+        let counter = |name, labels: &[(&str, &str)]| {
+            self.has(name, &[protocol])
+                .then(|| value(name, labels).unwrap_or_default() as u32)
+        };
+        let megabytes = |name| value(name, &[]).map(|bytes| (bytes / 1_000_000.0) as f32);
+        let offers = |direction| [("direction", direction), ("type", "offer")];
+        let accepts = |direction| [("direction", direction), ("type", "accept")];
+        let mut found = false;
+        found |= update(&mut data.offers_in, counter(MESSAGES, &offers("received")));
+        found |= update(&mut data.offers_out, counter(MESSAGES, &offers("sent")));
+        found |= update(
+            &mut data.accepts_in,
+            counter(MESSAGES, &accepts("received")),
+        );
+        found |= update(&mut data.accepts_out, counter(MESSAGES, &accepts("sent")));
+        // same as in trin's own report: successful / total validations
+        let successful = counter(VALIDATIONS, &[("success", "true")]);
+        found |= update(&mut data.validations_in, successful);
+        found |= update(&mut data.validations_out, counter(VALIDATIONS, &[]));
+        let count = value(ENTRY_COUNT, &[]).map(|count| count as u32);
+        found |= update(&mut data.count, count);
+        let radius = value(RADIUS_RATIO, &[]).map(|ratio| (ratio * 100.0) as f32);
+        found |= update(&mut data.radius, radius);
+        found |= update(&mut data.content_current, megabytes(CONTENT_STORAGE_USAGE));
+        found |= update(&mut data.content_total, megabytes(STORAGE_CAPACITY));
+        found |= update(&mut data.disk_usage, megabytes(TOTAL_STORAGE_USAGE));
+        found
+    }
+
+    fn apply_network(&self, network: &mut NetworkMetrics) {
+        let counter = |name, direction| {
+            self.has(name, &[]).then(|| {
+                self.sum(name, &[("direction", direction)])
+                    .unwrap_or_default() as u64
+            })
+        };
+        update(&mut network.messages_sent, counter(MESSAGES, "sent"));
+        update(
+            &mut network.messages_received,
+            counter(MESSAGES, "received"),
+        );
+        update(
+            &mut network.bytes_sent,
+            counter(UTP_PAYLOAD_BYTES, "outbound"),
+        );
+        update(
+            &mut network.bytes_received,
+            counter(UTP_PAYLOAD_BYTES, "inbound"),
+        );
+        let streams = self
+            .sum(UTP_ACTIVE_STREAMS, &[])
+            .map(|streams| streams as u64);
+        update(&mut network.active_streams, streams);
+        network.connected_peers = self.sum(CONNECTED_PEERS, &[]).map(|peers| peers as u64);
+        network.lookup_latency_ms = match (
+            self.sum(&format!("{LOOKUP_DURATION}_sum"), &[]),
+            self.sum(&format!("{LOOKUP_DURATION}_count"), &[]),
+        ) {
+            (Some(sum), Some(count)) if count > 0.0 => Some(sum / count * 1000.0),
+            _ => None,
+        };
+    }
+
+    // updates the stats with the metrics that were scraped. a scrape may be missing some
+    // metrics, eg. when trin hasn't registered them yet, so only the fields that were
+    // scraped are changed. the logs stay the source of the subnetwork data unless the
+    // metrics have some of it
+    pub fn apply(&self, stats: &mut NodeStats) {
+        let mut found = self.apply_subnetwork("state", &mut stats.state_data);
+        found |= self.apply_subnetwork("history", &mut stats.history_data);
+        found |= self.apply_subnetwork("beacon", &mut stats.beacon_data);
+        self.apply_network(&mut stats.network);
+        stats.stats_source = match found {
+            true => StatsSource::Metrics,
+            false => StatsSource::Logs,
+        };
+    }
+}
+
+// sets the field if there's a value for it, returns whether there was
+fn update<T>(field: &mut T, value: Option<T>) -> bool {
+    let found = value.is_some();
+    if let Some(value) = value {
+        *field = value;
+    }
+    found
+}
+
+// parses a sample line, eg. `trin_message_total{protocol="history",type="ping"} 12`
+fn parse_sample(line: &str) -> Option<MetricSample> {
+    let (name, rest) = match line.find(['{', ' ']) {
+        Some(i) => line.split_at(i),
+        None => return None,
+    };
+    let (labels, rest) = match rest.strip_prefix('{') {
+        Some(rest) => parse_labels(rest)?,
+        None => (Vec::new(), rest),
+    };
+    // the value may be followed by a timestamp
+    let value = rest.split_whitespace().next()?;
+    let value = match value {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        value => value.parse().ok()?,
+    };
+    Some(MetricSample {
+        name: name.to_string(),
+        labels,
+        value,
+    })
+}
+
+// parses the labels following the opening brace, returning them along with the rest of the line
+fn parse_labels(mut rest: &str) -> Option<(Vec<(String, String)>, &str)> {
+    let mut labels = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([' ', ',']);
+        if let Some(rest) = rest.strip_prefix('}') {
+            return Some((labels, rest));
+        }
+        let (label, after) = rest.split_once("=\"")?;
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (i, '"') => break i,
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.push((label.trim().to_string(), value));
+        rest = &after[end + 1..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METRICS: &str = r#"# HELP trin_radius_ratio the fraction of the whole data ring covered by the data radius
+# TYPE trin_radius_ratio gauge
+trin_radius_ratio{protocol="history"} 0.15
+trin_radius_ratio{protocol="state"} 1
+trin_entry_count{protocol="history"} 13763
+trin_content_storage_usage_bytes{protocol="history"} 116700000
+trin_storage_capacity_bytes{protocol="history"} 120000000
+trin_total_storage_usage_bytes{protocol="history"} 267100000
+# TYPE trin_message_total counter
+trin_message_total{protocol="history",direction="sent",type="offer"} 27338
+trin_message_total{protocol="history",direction="received",type="offer"} 26700
+trin_message_total{protocol="history",direction="sent",type="accept"} 11305
+trin_message_total{protocol="state",direction="received",type="ping"} 40
+trin_validation_total{protocol="history",success="true"} 6000
+trin_validation_total{protocol="history",success="false"} 50
+trin_utp_payload_bytes_total{protocol="history",direction="inbound"} 1048576
+trin_lookup_duration_seconds_bucket{protocol="history",le="+Inf"} 4
+trin_lookup_duration_seconds_sum{protocol="history"} 2.5
+trin_lookup_duration_seconds_count{protocol="history"} 4
+"#;
+
+    #[test]
+    fn test_parse_sample() {
+        let sample = parse_sample(
+            r#"trin_message_total{protocol="history",type="a \"quoted\" \\ value"} 3 1730000000"#,
+        )
+        .unwrap();
+        assert_eq!(sample.name, "trin_message_total");
+        assert_eq!(
+            sample.labels,
+            vec![
+                ("protocol".to_string(), "history".to_string()),
+                ("type".to_string(), r#"a "quoted" \ value"#.to_string()),
+            ]
+        );
+        assert_eq!(sample.value, 3.0);
+
+        let sample = parse_sample("process_open_fds 42").unwrap();
+        assert!(sample.labels.is_empty());
+        assert_eq!(sample.value, 42.0);
+        assert!(parse_sample("not a sample").is_none());
+    }
+
+    #[test]
+    fn test_apply_metrics_to_node_stats() {
+        let mut stats = NodeStats::default();
+        Metrics::parse(METRICS).apply(&mut stats);
+
+        assert_eq!(stats.stats_source, StatsSource::Metrics);
+        assert_eq!(stats.history_data.radius, 15.0);
+        assert_eq!(stats.history_data.count, 13763);
+        assert_eq!(stats.history_data.content_current, 116.7);
+        assert_eq!(stats.history_data.content_total, 120.0);
+        assert_eq!(stats.history_data.disk_usage, 267.1);
+        assert_eq!(stats.history_data.offers_in, 26700);
+        assert_eq!(stats.history_data.offers_out, 27338);
+        assert_eq!(stats.history_data.accepts_out, 11305);
+        assert_eq!(stats.history_data.validations_in, 6000);
+        assert_eq!(stats.history_data.validations_out, 6050);
+        assert_eq!(stats.state_data.radius, 100.0);
+
+        assert_eq!(stats.network.connected_peers, None);
+        assert_eq!(stats.network.messages_sent, 27338 + 11305);
+        assert_eq!(stats.network.messages_received, 26700 + 40);
+        assert_eq!(stats.network.bytes_received, 1048576);
+        assert_eq!(stats.network.lookup_latency_ms, Some(625.0));
+    }
+
+    #[test]
+    fn test_partial_scrape_keeps_stats() {
+        let mut stats = NodeStats::default();
+        Metrics::parse(METRICS).apply(&mut stats);
+
+        // trin hasn't registered its storage metrics yet
+        Metrics::parse(
+            r#"trin_message_total{protocol="history",direction="received",type="offer"} 26800"#,
+        )
+        .apply(&mut stats);
+        assert_eq!(stats.stats_source, StatsSource::Metrics);
+        assert_eq!(stats.history_data.offers_in, 26800);
+        // there's no offer sent in this scrape, but the message counter is there
+        assert_eq!(stats.history_data.offers_out, 0);
+        assert_eq!(stats.history_data.radius, 15.0);
+        assert_eq!(stats.history_data.count, 13763);
+        assert_eq!(stats.history_data.validations_in, 6000);
+
+        // without any subnetwork metrics, the logs are used again
+        Metrics::parse("process_open_fds 42").apply(&mut stats);
+        assert_eq!(stats.stats_source, StatsSource::Logs);
+        assert_eq!(stats.history_data.radius, 15.0);
+    }
+}
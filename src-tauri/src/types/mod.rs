@@ -1,2 +1,19 @@
+pub mod activity;
+pub mod alerts;
+pub mod app_config;
+pub mod checkpoint;
 pub mod config;
+pub mod crash_report;
+pub mod exporter;
+pub mod limits;
+pub mod log_buffer;
+pub mod log_parser;
+pub mod metrics;
 pub mod node;
+pub mod node_state;
+pub mod pid_file;
+pub mod ports;
+pub mod readiness;
+pub mod stats_history;
+pub mod storage;
+pub mod supervisor;
\ No newline at end of file
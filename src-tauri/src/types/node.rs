@@ -1,5 +1,16 @@
+use crate::types::activity::SubnetworkActivity;
+use crate::types::metrics::{NetworkMetrics, StatsSource};
+use crate::types::storage::StorageStats;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+static SUBNETWORK_DATA_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"radius=(\d+\.?\d*)%.*?content=(\d+\.?\d*)/(\d+\.?\d*)mb.*?#=(\d+).*?disk=(\d+\.?\d*)mb.*?offers=(\d+)/(\d+).*?accepts=(\d+)/(\d+).*?validations=(\d+)/(\d+)",
+    )
+    .expect("invalid subnetwork data regex")
+});
 
 // all of the node stats that are displayed in the trin desktop app
 #[derive(Clone, Copy, Deserialize, Serialize, Default)]
@@ -7,15 +18,36 @@ use serde::{Deserialize, Serialize};
 pub struct NodeStats {
     pub cpu: f32,
     pub pid: usize,
+    // the resources used by trin and its child processes, memory is in bytes
+    pub memory: u64,
+    pub virtual_memory: u64,
+    pub threads: Option<usize>,
+    pub open_fds: Option<usize>,
+    // bytes per second
+    pub disk_read_rate: u64,
+    pub disk_write_rate: u64,
     pub state_data: SubnetworkDataLog,
     pub history_data: SubnetworkDataLog,
     pub beacon_data: SubnetworkDataLog,
+    // rates & lifetime totals, derived from the cumulative counters in the data above
+    pub state_activity: SubnetworkActivity,
+    pub history_activity: SubnetworkActivity,
+    pub beacon_activity: SubnetworkActivity,
+    // the disk space used by trin, split up by subnetwork. unlike the disk usage in
+    // the subnetwork data, which is the same database-wide figure for every subnetwork
+    pub storage: StorageStats,
     pub latest_finalized_block: u64,
     pub latest_optimistic_block: u64,
+    // the number of recognized log lines that couldn't be parsed, this going up
+    // usually means that trin changed the format of its logs
+    pub log_parse_failures: u64,
+    // the subnetwork data is read from trin's metrics, unless they're unavailable
+    pub stats_source: StatsSource,
+    pub network: NetworkMetrics,
 }
 
-// the scraped stats from trin's "trin_*: reports~ data:" log line
-// in the future, we should probably just add a jsonrpc endpoint that returns this data
+// the stats of a single subnetwork, read from trin's metrics. older trin binaries
+// only report these in their "trin_*: reports~ data:" log line, which is used as a fallback
 #[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
 pub struct SubnetworkDataLog {
     pub radius: f32,
@@ -33,11 +65,7 @@ pub struct SubnetworkDataLog {
 
 impl SubnetworkDataLog {
     pub fn parse_log_line(line: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let re = Regex::new(
-            r"radius=(\d+\.?\d*)%.*?content=(\d+\.?\d*)/(\d+\.?\d*)mb.*?#=(\d+).*?disk=(\d+\.?\d*)mb.*?offers=(\d+)/(\d+).*?accepts=(\d+)/(\d+).*?validations=(\d+)/(\d+)",
-        )?;
-
-        let captures = re
+        let captures = SUBNETWORK_DATA_RE
             .captures(line)
             .ok_or("Failed to match log line pattern")?;
 
@@ -63,7 +91,7 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
-    #[case("[2024-10-31][19:13:41][trin_desktop_lib][INFO] Child process stdout: 2024-10-31T19:13:41.425824Z  INFO trin_history: reports~ data: radius=15% content=116.7/120mb #=13763 disk=267.1mb; msgs: offers=0/0, accepts=0/0, validations=0/0", NodeHistoryLog {
+    #[case("[2024-10-31][19:13:41][trin_desktop_lib][INFO] Child process stdout: 2024-10-31T19:13:41.425824Z  INFO trin_history: reports~ data: radius=15% content=116.7/120mb #=13763 disk=267.1mb; msgs: offers=0/0, accepts=0/0, validations=0/0", SubnetworkDataLog {
         radius: 15.0,
         content_current: 116.7,
         content_total: 120.0,
@@ -76,7 +104,7 @@ mod tests {
         validations_in: 0,
         validations_out: 0,
     })]
-    #[case("[2024-10-31][19:57:52][trin_desktop_lib][INFO] Child process stdout: 2024-10-31T19:57:52.530059Z  INFO trin_history: reports~ data: radius=8.8% content=117.7/120mb #=11719 disk=267.1mb; msgs: offers=26700/27338, accepts=11305/11305, validations=6050/6050", NodeHistoryLog {
+    #[case("[2024-10-31][19:57:52][trin_desktop_lib][INFO] Child process stdout: 2024-10-31T19:57:52.530059Z  INFO trin_history: reports~ data: radius=8.8% content=117.7/120mb #=11719 disk=267.1mb; msgs: offers=26700/27338, accepts=11305/11305, validations=6050/6050", SubnetworkDataLog {
         radius: 8.8,
         content_current: 117.7,
         content_total: 120.0,
@@ -89,8 +117,8 @@ mod tests {
         validations_in: 6050,
         validations_out: 6050,
     })]
-    fn test_parse_log_lines(#[case] log: &str, #[case] expected: NodeHistoryLog) {
-        let result = NodeHistoryLog::parse_log_line(log).unwrap();
+    fn test_parse_log_lines(#[case] log: &str, #[case] expected: SubnetworkDataLog) {
+        let result = SubnetworkDataLog::parse_log_line(log).unwrap();
         assert_eq!(result.radius, expected.radius);
         assert_eq!(result.content_current, expected.content_current);
         assert_eq!(result.content_total, expected.content_total);
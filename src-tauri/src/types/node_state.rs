@@ -0,0 +1,84 @@
+use crate::types::readiness::ReadinessStage;
+use serde::Serialize;
+
+// the lifecycle of the trin node, owned by the backend so that it survives
+// reloading the webview and can't drift from what the sidecar is actually doing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeState {
+    #[default]
+    Stopped,
+    Starting,
+    Running,
+    // trin is responding to rpc requests, but isn't participating in the network
+    Degraded,
+    Stopping,
+    Crashed,
+}
+
+impl NodeState {
+    pub fn can_transition_to(self, next: NodeState) -> bool {
+        use NodeState::*;
+        matches!(
+            (self, next),
+            (Stopped | Crashed, Starting)
+                // a launch that failed before trin was spawned goes back to stopped
+                | (Starting, Running | Stopped | Stopping | Crashed)
+                | (Running, Degraded)
+                | (Degraded, Running)
+                | (Running | Degraded, Stopping | Crashed)
+                | (Stopping, Stopped)
+                // a crashed node is stopped by cancelling its pending restart
+                | (Crashed, Stopped)
+        )
+    }
+
+    // whether trin is up, a degraded node is still running and serving rpc requests
+    pub fn is_up(self) -> bool {
+        matches!(self, NodeState::Running | NodeState::Degraded)
+    }
+}
+
+// payload of the "trin-node-state" event, emitted on every state transition
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStateEvent {
+    pub state: NodeState,
+    pub previous: NodeState,
+}
+
+// returned by the "get_node_status" command
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatus {
+    pub state: NodeState,
+    pub readiness: ReadinessStage,
+    pub pid: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(NodeState::Stopped, NodeState::Starting, true)]
+    #[case(NodeState::Crashed, NodeState::Starting, true)]
+    #[case(NodeState::Starting, NodeState::Running, true)]
+    #[case(NodeState::Starting, NodeState::Stopped, true)]
+    #[case(NodeState::Running, NodeState::Degraded, true)]
+    #[case(NodeState::Degraded, NodeState::Crashed, true)]
+    #[case(NodeState::Stopping, NodeState::Stopped, true)]
+    #[case(NodeState::Starting, NodeState::Starting, false)]
+    #[case(NodeState::Running, NodeState::Starting, false)]
+    #[case(NodeState::Stopping, NodeState::Starting, false)]
+    #[case(NodeState::Stopped, NodeState::Running, false)]
+    #[case(NodeState::Stopped, NodeState::Crashed, false)]
+    fn test_node_state_transitions(
+        #[case] from: NodeState,
+        #[case] to: NodeState,
+        #[case] valid: bool,
+    ) {
+        assert_eq!(from.can_transition_to(to), valid);
+    }
+}
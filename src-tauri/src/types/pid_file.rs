@@ -0,0 +1,72 @@
+use crate::types::config::TrinConfig;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const PID_FILE: &str = "trin.pid";
+
+// written to the app data dir whenever trin is spawned, so that a trin process
+// left behind by a killed app can be found (and adopted) on the next startup
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PidFile {
+    pub pid: u32,
+    // the pid may have been reused by another process since, the start time
+    // (in seconds since the epoch) tells them apart
+    pub start_time: u64,
+    pub trin_config: TrinConfig,
+}
+
+impl PidFile {
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let pid_file = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(PID_FILE), pid_file).map_err(|e| e.to_string())
+    }
+
+    pub fn load(dir: &Path) -> Result<Option<Self>, String> {
+        let path = dir.join(PID_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let pid_file = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&pid_file).map_err(|e| e.to_string())
+    }
+
+    // removes the pid file, but only if it still belongs to the given process
+    pub fn remove(dir: &Path, pid: u32) -> Result<(), String> {
+        match Self::load(dir)? {
+            Some(pid_file) if pid_file.pid == pid => {
+                std::fs::remove_file(dir.join(PID_FILE)).map_err(|e| e.to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_only_matching_pid_file() {
+        let dir = std::env::temp_dir().join("trin-desktop-pid-file-test");
+        let pid_file = PidFile {
+            pid: 1234,
+            start_time: 1730000000,
+            trin_config: serde_json::from_str(
+                r#"{"httpPort": 8545, "storage": 2000, "trustedBlockRoot": "0x"}"#,
+            )
+            .unwrap(),
+        };
+        pid_file.save(&dir).unwrap();
+
+        PidFile::remove(&dir, 4321).unwrap();
+        let loaded = PidFile::load(&dir).unwrap().unwrap();
+        assert_eq!(loaded.pid, 1234);
+        assert_eq!(loaded.trin_config.httpPort, 8545);
+
+        PidFile::remove(&dir, 1234).unwrap();
+        assert!(PidFile::load(&dir).unwrap().is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
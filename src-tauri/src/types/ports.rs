@@ -0,0 +1,47 @@
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+// the process that is bound to a port we need, if we could find it
+#[derive(Clone, Debug, Serialize)]
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: String,
+}
+
+// a port that trin needs, but that is already taken
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortConflict {
+    // which trin setting the port belongs to, eg. "httpPort"
+    pub field: String,
+    pub port: u16,
+    pub protocol: Protocol,
+    pub owner: Option<PortOwner>,
+    // the nearest ports that are free, closest first
+    pub suggestions: Vec<u16>,
+}
+
+impl fmt::Display for PortConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let protocol = match self.protocol {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        };
+        write!(f, "{protocol} port {} is already in use", self.port)?;
+        if let Some(owner) = &self.owner {
+            write!(f, " by {} (pid {})", owner.name, owner.pid)?;
+        }
+        if !self.suggestions.is_empty() {
+            let suggestions: Vec<String> = self.suggestions.iter().map(u16::to_string).collect();
+            write!(f, ", try {} instead", suggestions.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+use serde_json::Value;
+
+// how far along the trin node is, from merely running to actually participating in
+// the network. the stages are ordered, and every stage requires the previous ones
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReadinessStage {
+    #[default]
+    NotStarted,
+    ProcessSpawned,
+    RpcBound,
+    Discv5Bound,
+    PeerFound,
+    BeaconSynced,
+}
+
+// the results of the probes that are used to determine the readiness stage
+#[derive(Debug, Default)]
+pub struct ReadinessProbes {
+    pub process_running: bool,
+    pub rpc_bound: bool,
+    pub discv5_bound: bool,
+    pub peers: usize,
+    // also set when the beacon subnetwork is disabled, since there's nothing to sync
+    pub beacon_synced: bool,
+}
+
+impl ReadinessStage {
+    pub fn from_probes(probes: &ReadinessProbes) -> Self {
+        if !probes.process_running {
+            Self::NotStarted
+        } else if !probes.rpc_bound {
+            Self::ProcessSpawned
+        } else if !probes.discv5_bound {
+            Self::RpcBound
+        } else if probes.peers == 0 {
+            Self::Discv5Bound
+        } else if !probes.beacon_synced {
+            Self::PeerFound
+        } else {
+            Self::BeaconSynced
+        }
+    }
+}
+
+// payload of the "trin-readiness" event, emitted whenever the stage changes
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessEvent {
+    pub stage: ReadinessStage,
+    pub previous: ReadinessStage,
+}
+
+// counts the peers in a "discv5_routingTableInfo" response. the exact shape of the
+// buckets has changed between trin versions, but every peer comes with its enr
+pub fn count_peers(routing_table: &Value) -> usize {
+    fn count_enrs(value: &Value) -> usize {
+        match value {
+            Value::String(value) => value.starts_with("enr:") as usize,
+            Value::Array(values) => values.iter().map(count_enrs).sum(),
+            Value::Object(values) => values.values().map(count_enrs).sum(),
+            _ => 0,
+        }
+    }
+    count_enrs(&routing_table["buckets"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case(false, false, false, 0, false, ReadinessStage::NotStarted)]
+    #[case(true, false, false, 0, false, ReadinessStage::ProcessSpawned)]
+    #[case(true, true, false, 3, true, ReadinessStage::RpcBound)]
+    #[case(true, true, true, 0, true, ReadinessStage::Discv5Bound)]
+    #[case(true, true, true, 3, false, ReadinessStage::PeerFound)]
+    #[case(true, true, true, 3, true, ReadinessStage::BeaconSynced)]
+    fn test_readiness_stage_from_probes(
+        #[case] process_running: bool,
+        #[case] rpc_bound: bool,
+        #[case] discv5_bound: bool,
+        #[case] peers: usize,
+        #[case] beacon_synced: bool,
+        #[case] expected: ReadinessStage,
+    ) {
+        let probes = ReadinessProbes {
+            process_running,
+            rpc_bound,
+            discv5_bound,
+            peers,
+            beacon_synced,
+        };
+        assert_eq!(ReadinessStage::from_probes(&probes), expected);
+    }
+
+    #[test]
+    fn test_count_peers() {
+        let routing_table = json!({
+            "localNodeId": "0x1234",
+            "buckets": [
+                [["0xaa", "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04j", "Connected"]],
+                [],
+                [
+                    ["0xbb", "enr:-IS4QLkKqDMy_ExrpOEWa59NiClemOnor-krjp4qoeZwIw2QduPC", "Connected"],
+                    ["0xcc", "enr:-IS4QGUtAA29qeT3cWVr8lmJfySmkceR2wp6oFQtvO_uMe7KWaK_", "Disconnected"]
+                ]
+            ]
+        });
+        assert_eq!(count_peers(&routing_table), 3);
+        assert_eq!(
+            count_peers(&json!({ "localNodeId": "0x1234", "buckets": [] })),
+            0
+        );
+    }
+}
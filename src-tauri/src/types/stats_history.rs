@@ -0,0 +1,558 @@
+use crate::types::node::NodeStats;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const STATS_HISTORY_DIR: &str = "stats_history";
+
+// the downsampling tiers of the stats history, from finest to coarsest
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Resolution {
+    // every sample, as taken by the stats probe
+    Raw,
+    Minute,
+    Hour,
+}
+
+impl Resolution {
+    const ALL: [Resolution; 3] = [Self::Raw, Self::Minute, Self::Hour];
+
+    // how long the samples of this tier are kept, in seconds
+    fn retention(self) -> u64 {
+        match self {
+            Self::Raw => 60 * 60,
+            Self::Minute => 7 * 24 * 60 * 60,
+            Self::Hour => 365 * 24 * 60 * 60,
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw.jsonl",
+            Self::Minute => "minute.jsonl",
+            Self::Hour => "hour.jsonl",
+        }
+    }
+}
+
+// a single point of a metric's history, as returned by "get_stats_history"
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StatsPoint {
+    // unix timestamp in seconds, for downsampled tiers this is the start of the bucket
+    pub timestamp: u64,
+    pub value: f64,
+}
+
+// the values of all metrics at one point in time, in the order of `StatsHistory::metrics`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Sample {
+    timestamp: u64,
+    values: Vec<Option<f64>>,
+}
+
+// the first line of every tier file, so that samples can still be read after
+// the metrics in `NodeStats` have changed
+#[derive(Deserialize, Serialize)]
+struct Header {
+    metrics: Vec<String>,
+}
+
+// averages the samples that fall into the same bucket
+struct Accumulator {
+    bucket: u64,
+    sums: Vec<f64>,
+    counts: Vec<u32>,
+}
+
+impl Accumulator {
+    fn new(bucket: u64, metrics: usize) -> Self {
+        Self {
+            bucket,
+            sums: vec![0.0; metrics],
+            counts: vec![0; metrics],
+        }
+    }
+
+    fn add(&mut self, sample: &Sample) {
+        for (i, value) in sample.values.iter().enumerate() {
+            if let Some(value) = value {
+                self.sums[i] += value;
+                self.counts[i] += 1;
+            }
+        }
+    }
+
+    fn average(&self) -> Sample {
+        let values = self
+            .sums
+            .iter()
+            .zip(&self.counts)
+            .map(|(sum, count)| (*count > 0).then(|| sum / *count as f64))
+            .collect();
+        Sample {
+            timestamp: self.bucket,
+            values,
+        }
+    }
+}
+
+struct Tier {
+    resolution: Resolution,
+    samples: VecDeque<Sample>,
+    // the number of samples in the tier file, which is compacted once it has grown
+    // well beyond the samples that are still retained
+    samples_on_disk: usize,
+    // the number of newest samples that haven't been written to the file yet
+    unsaved: usize,
+    // the file has to be rewritten, eg. because it was written with other metrics
+    rewrite: bool,
+}
+
+impl Tier {
+    fn new(resolution: Resolution, samples: VecDeque<Sample>) -> Self {
+        Self {
+            resolution,
+            samples_on_disk: samples.len(),
+            samples,
+            unsaved: 0,
+            rewrite: false,
+        }
+    }
+}
+
+// the changes to the tier files since they were last saved. they're written by
+// `apply`, which does the io, so that it can be done without holding on to the history
+pub struct StatsWrites(Vec<(PathBuf, FileWrite)>);
+
+enum FileWrite {
+    Append(String),
+    Replace(String),
+}
+
+impl StatsWrites {
+    pub fn apply(self) -> Result<(), String> {
+        for (path, write) in self.0 {
+            match write {
+                FileWrite::Append(lines) => {
+                    // the file always exists, it's written in full when the history is loaded
+                    let mut file = OpenOptions::new()
+                        .append(true)
+                        .open(&path)
+                        .map_err(|e| format!("{}: {e}", path.display()))?;
+                    file.write_all(lines.as_bytes())
+                        .map_err(|e| format!("{}: {e}", path.display()))?;
+                }
+                FileWrite::Replace(contents) => std::fs::write(&path, contents)
+                    .map_err(|e| format!("{}: {e}", path.display()))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// the history of the metrics in `METRICS`. samples are kept in memory for queries, and
+// saved to a file per tier in the app data dir every so often, so that they survive
+// restarting the app
+pub struct StatsHistory {
+    metrics: Vec<String>,
+    tiers: Vec<Tier>,
+    // the buckets that are currently being averaged into the minute & hour tiers
+    minute: Option<Accumulator>,
+    hour: Option<Accumulator>,
+    dir: Option<PathBuf>,
+}
+
+impl StatsHistory {
+    pub fn load(dir: &Path) -> Self {
+        let dir = dir.join(STATS_HISTORY_DIR);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            warn!("unable to create stats history dir: {e}");
+        }
+        let metrics = metric_names();
+        let tiers = Resolution::ALL
+            .into_iter()
+            .map(|resolution| {
+                let path = dir.join(resolution.file_name());
+                let samples = read_samples(&path, &metrics).unwrap_or_else(|e| {
+                    warn!(
+                        "unable to read {} stats history: {e}",
+                        resolution.file_name()
+                    );
+                    Vec::new()
+                });
+                Tier::new(resolution, samples.into())
+            })
+            .collect();
+        let mut history = Self {
+            metrics,
+            tiers,
+            minute: None,
+            hour: None,
+            dir: Some(dir),
+        };
+        // drop expired samples, and rewrite the files with the current metrics
+        for tier in &mut history.tiers {
+            tier.rewrite = true;
+        }
+        if let Err(e) = history.take_writes().apply() {
+            warn!("unable to compact stats history: {e}");
+        }
+        history
+    }
+
+    pub fn record(&mut self, timestamp: u64, stats: &NodeStats) {
+        let sample = Sample {
+            timestamp,
+            values: metric_values(stats),
+        };
+
+        let minute = timestamp - timestamp % 60;
+        let finished_minute = finish_bucket(&mut self.minute, minute, self.metrics.len());
+        if let Some(minute_sample) = finished_minute {
+            let hour = minute_sample.timestamp - minute_sample.timestamp % 3600;
+            let finished_hour = finish_bucket(&mut self.hour, hour, self.metrics.len());
+            self.hour.as_mut().unwrap().add(&minute_sample);
+            self.push(1, minute_sample);
+            if let Some(hour_sample) = finished_hour {
+                self.push(2, hour_sample);
+            }
+        }
+        self.minute.as_mut().unwrap().add(&sample);
+        self.push(0, sample);
+    }
+
+    // the history of a single metric. without a resolution, the finest tier that
+    // still covers `from` is used
+    pub fn query(
+        &self,
+        metric: &str,
+        from: u64,
+        to: u64,
+        resolution: Option<Resolution>,
+        now: u64,
+    ) -> Result<Vec<StatsPoint>, String> {
+        let metrics = &self.metrics;
+        let index = metrics
+            .iter()
+            .position(|name| name == metric)
+            .ok_or_else(|| {
+                format!(
+                    "unknown metric {metric}, expected one of: {}",
+                    metrics.join(", ")
+                )
+            })?;
+        let resolution = resolution.unwrap_or_else(|| {
+            Resolution::ALL
+                .into_iter()
+                .find(|resolution| now.saturating_sub(resolution.retention()) <= from)
+                .unwrap_or(Resolution::Hour)
+        });
+        let Some(tier) = self.tiers.iter().find(|tier| tier.resolution == resolution) else {
+            return Ok(Vec::new());
+        };
+        Ok(tier
+            .samples
+            .iter()
+            .filter(|sample| (from..=to).contains(&sample.timestamp))
+            .filter_map(|sample| {
+                Some(StatsPoint {
+                    timestamp: sample.timestamp,
+                    value: (*sample.values.get(index)?)?,
+                })
+            })
+            .collect())
+    }
+
+    fn push(&mut self, tier: usize, sample: Sample) {
+        let tier = &mut self.tiers[tier];
+        let oldest = sample.timestamp.saturating_sub(tier.resolution.retention());
+        while tier
+            .samples
+            .front()
+            .is_some_and(|sample| sample.timestamp < oldest)
+        {
+            tier.samples.pop_front();
+        }
+        tier.samples.push_back(sample);
+        tier.unsaved = (tier.unsaved + 1).min(tier.samples.len());
+    }
+
+    // takes the samples that haven't been saved yet. a tier file is rewritten with only
+    // the samples that are still retained, once it has grown well beyond them
+    pub fn take_writes(&mut self) -> StatsWrites {
+        let Some(dir) = &self.dir else {
+            return StatsWrites(Vec::new());
+        };
+        let mut writes = Vec::new();
+        for tier in &mut self.tiers {
+            let path = dir.join(tier.resolution.file_name());
+            let on_disk = tier.samples_on_disk + tier.unsaved;
+            if tier.rewrite || on_disk > 2 * tier.samples.len() + 100 {
+                let header = Header {
+                    metrics: self.metrics.clone(),
+                };
+                let contents = std::iter::once(serde_json::to_string(&header))
+                    .chain(tier.samples.iter().map(serde_json::to_string))
+                    .map(|line| line.map(|line| line + "\n"))
+                    .collect::<Result<String, _>>();
+                match contents {
+                    Ok(contents) => writes.push((path, FileWrite::Replace(contents))),
+                    Err(e) => warn!("unable to serialize stats history: {e}"),
+                }
+                tier.samples_on_disk = tier.samples.len();
+            } else if tier.unsaved > 0 {
+                let lines = tier
+                    .samples
+                    .range(tier.samples.len() - tier.unsaved..)
+                    .map(|sample| serde_json::to_string(sample).map(|line| line + "\n"))
+                    .collect::<Result<String, _>>();
+                match lines {
+                    Ok(lines) => writes.push((path, FileWrite::Append(lines))),
+                    Err(e) => warn!("unable to serialize stats history: {e}"),
+                }
+                tier.samples_on_disk = on_disk;
+            }
+            tier.unsaved = 0;
+            tier.rewrite = false;
+        }
+        StatsWrites(writes)
+    }
+}
+
+impl Default for StatsHistory {
+    // a history that is only kept in memory
+    fn default() -> Self {
+        Self {
+            metrics: metric_names(),
+            tiers: Resolution::ALL
+                .into_iter()
+                .map(|resolution| Tier::new(resolution, VecDeque::new()))
+                .collect(),
+            minute: None,
+            hour: None,
+            dir: None,
+        }
+    }
+}
+
+// starts a new bucket if the timestamp has moved past the current one, returning the
+// average of the bucket that was finished
+fn finish_bucket(
+    accumulator: &mut Option<Accumulator>,
+    bucket: u64,
+    metrics: usize,
+) -> Option<Sample> {
+    match accumulator {
+        Some(current) if current.bucket == bucket => None,
+        _ => accumulator
+            .replace(Accumulator::new(bucket, metrics))
+            .map(|finished| finished.average()),
+    }
+}
+
+// reads the samples in a tier file, mapping them onto the current metrics
+fn read_samples(path: &Path, metrics: &[String]) -> Result<Vec<Sample>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut lines = BufReader::new(File::open(path).map_err(|e| e.to_string())?).lines();
+    let Some(header) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Header =
+        serde_json::from_str(&header.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    let indices: Vec<Option<usize>> = metrics
+        .iter()
+        .map(|metric| header.metrics.iter().position(|name| name == metric))
+        .collect();
+    Ok(lines
+        .map_while(Result::ok)
+        // skip lines that are cut off, eg. because the app was killed while writing
+        .filter_map(|line| serde_json::from_str::<Sample>(&line).ok())
+        .map(|sample| Sample {
+            timestamp: sample.timestamp,
+            values: indices
+                .iter()
+                .map(|index| index.and_then(|index| sample.values.get(index).copied().flatten()))
+                .collect(),
+        })
+        .collect())
+}
+
+// the metrics that are recorded, by their path in the "trin-stats" event. the counters
+// that only ever go up, and the figures that are derived from others, aren't worth
+// keeping a history of
+const METRICS: [&str; 37] = [
+    "cpu",
+    "memory",
+    "virtualMemory",
+    "threads",
+    "openFds",
+    "diskReadRate",
+    "diskWriteRate",
+    "stateData.radius",
+    "stateData.content_current",
+    "stateData.count",
+    "stateData.disk_usage",
+    "historyData.radius",
+    "historyData.content_current",
+    "historyData.count",
+    "historyData.disk_usage",
+    "beaconData.radius",
+    "beaconData.content_current",
+    "beaconData.count",
+    "beaconData.disk_usage",
+    "stateActivity.rates.offersIn",
+    "stateActivity.rates.offersOut",
+    "stateActivity.rates.acceptsIn",
+    "stateActivity.rates.validationsIn",
+    "historyActivity.rates.offersIn",
+    "historyActivity.rates.offersOut",
+    "historyActivity.rates.acceptsIn",
+    "historyActivity.rates.validationsIn",
+    "beaconActivity.rates.offersIn",
+    "beaconActivity.rates.offersOut",
+    "beaconActivity.rates.acceptsIn",
+    "beaconActivity.rates.validationsIn",
+    "network.connectedPeers",
+    "network.lookupLatencyMs",
+    "storage.diskUsage",
+    "storage.budgetUsed",
+    "storage.growthRate",
+    "latestFinalizedBlock",
+];
+
+pub fn metric_names() -> Vec<String> {
+    METRICS.map(String::from).to_vec()
+}
+
+// the values of the metrics in `METRICS`, None for the ones that aren't available
+fn metric_values(stats: &NodeStats) -> Vec<Option<f64>> {
+    let stats = serde_json::to_value(stats).unwrap_or_default();
+    METRICS
+        .iter()
+        .map(|metric| {
+            metric
+                .split('.')
+                .try_fold(&stats, |value, field| value.get(field))
+                .and_then(Value::as_f64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(radius: f32) -> NodeStats {
+        let mut stats = NodeStats::default();
+        stats.history_data.radius = radius;
+        stats
+    }
+
+    #[test]
+    fn test_stats_are_downsampled() {
+        let mut history = StatsHistory::default();
+        let start = 1_730_000_000 - 1_730_000_000 % 3600;
+        // two hours of samples, every 3 seconds, with the radius going up every minute
+        for timestamp in (start..start + 2 * 3600).step_by(3) {
+            history.record(timestamp, &stats(((timestamp - start) / 60) as f32));
+        }
+        let now = start + 2 * 3600;
+
+        let raw = history
+            .query("historyData.radius", start, now, Some(Resolution::Raw), now)
+            .unwrap();
+        // only the last hour of raw samples is kept
+        assert_eq!(raw.len(), 1201);
+        assert_eq!(raw[0].timestamp, start + 3600 - 3);
+
+        let minutes = history
+            .query("historyData.radius", start, now, None, now)
+            .unwrap();
+        assert_eq!(minutes.len(), 119);
+        assert_eq!(
+            minutes[1],
+            StatsPoint {
+                timestamp: start + 60,
+                value: 1.0
+            }
+        );
+
+        let hours = history
+            .query(
+                "historyData.radius",
+                start,
+                now,
+                Some(Resolution::Hour),
+                now,
+            )
+            .unwrap();
+        assert_eq!(
+            hours,
+            vec![StatsPoint {
+                timestamp: start,
+                value: 29.5
+            }]
+        );
+
+        assert!(history.query("unknown", start, now, None, now).is_err());
+    }
+
+    #[test]
+    fn test_metrics_exist() {
+        // every metric has to be a numeric field of the stats, or an optional one
+        let stats = serde_json::to_value(NodeStats::default()).unwrap();
+        for metric in METRICS {
+            let value = metric
+                .split('.')
+                .try_fold(&stats, |value, field| value.get(field));
+            assert!(
+                value.is_some_and(|value| value.is_number() || value.is_null()),
+                "{metric}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stats_history_is_persisted() {
+        let dir = std::env::temp_dir().join("trin-desktop-stats-history-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let start = 1_730_000_000;
+        {
+            let mut history = StatsHistory::load(&dir);
+            for timestamp in (start..start + 300).step_by(3) {
+                history.record(timestamp, &stats(12.5));
+            }
+            // nothing is written until the writes are taken
+            let raw = dir.join(STATS_HISTORY_DIR).join("raw.jsonl");
+            assert_eq!(std::fs::read_to_string(&raw).unwrap().lines().count(), 1);
+            history.take_writes().apply().unwrap();
+        }
+
+        let history = StatsHistory::load(&dir);
+        let now = start + 300;
+        let raw = history
+            .query("historyData.radius", start, now, Some(Resolution::Raw), now)
+            .unwrap();
+        assert_eq!(raw.len(), 100);
+        assert!(raw.iter().all(|point| point.value == 12.5));
+        let minutes = history
+            .query(
+                "historyData.radius",
+                start - 60,
+                now,
+                Some(Resolution::Minute),
+                now,
+            )
+            .unwrap();
+        // the minute that's still in progress isn't saved yet
+        assert_eq!(minutes.len(), 5);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
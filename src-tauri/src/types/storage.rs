@@ -0,0 +1,160 @@
+use crate::types::config::BYTES_PER_MB;
+use crate::types::node::NodeStats;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// the growth of the data dir is averaged over this window, in seconds
+const GROWTH_WINDOW: u64 = 60 * 60;
+
+// no projection is made until the samples cover at least this long, in seconds,
+// otherwise a single burst of writes would predict a full disk within minutes
+const MIN_GROWTH_SPAN: u64 = 10 * 60;
+
+// how much disk space trin uses, and how long until it runs out of its budget
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageStats {
+    // total size of trin's data dir, in bytes. None until it has been measured
+    pub disk_usage: Option<u64>,
+    // the content stored for each subnetwork, in bytes
+    pub content: StorageBreakdown,
+    // the storage configured with trin's --mb flag, in bytes
+    pub budget: u64,
+    // percentage of the budget used by the data dir
+    pub budget_used: Option<f64>,
+    // bytes per second, averaged over the last hour
+    pub growth_rate: Option<f64>,
+    // projected from the growth rate, None while the data dir isn't growing
+    pub time_to_full_secs: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageBreakdown {
+    pub state: u64,
+    pub history: u64,
+    pub beacon: u64,
+}
+
+impl StorageBreakdown {
+    pub fn total(&self) -> u64 {
+        self.state + self.history + self.beacon
+    }
+}
+
+// keeps the recent sizes of trin's data dir, to work out how fast it is growing
+#[derive(Debug, Default)]
+pub struct StorageTracker {
+    // (timestamp, bytes) pairs within the growth window
+    samples: VecDeque<(u64, u64)>,
+}
+
+impl StorageTracker {
+    // records a new measurement of the data dir, which is too slow to do on every update
+    pub fn record(&mut self, timestamp: u64, disk_usage: u64) {
+        while let Some((oldest, _)) = self.samples.front() {
+            if timestamp.saturating_sub(*oldest) > GROWTH_WINDOW {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.samples.push_back((timestamp, disk_usage));
+    }
+
+    // the data of a previous trin session doesn't say anything about this one's growth
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    // fills in the storage stats from the latest measurement & the subnetwork data
+    pub fn update(&self, stats: &mut NodeStats, storage_mb: usize) {
+        let megabytes = |mb: f32| (mb as f64 * BYTES_PER_MB as f64) as u64;
+        let content = StorageBreakdown {
+            state: megabytes(stats.state_data.content_current),
+            history: megabytes(stats.history_data.content_current),
+            beacon: megabytes(stats.beacon_data.content_current),
+        };
+        let budget = storage_mb as u64 * BYTES_PER_MB;
+        let disk_usage = self.samples.back().map(|(_, bytes)| *bytes);
+        // until the data dir has been measured, the content is the best estimate we have
+        let used = disk_usage.unwrap_or(content.total());
+        let growth_rate = self.growth_rate();
+        let time_to_full_secs = growth_rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| (budget.saturating_sub(used) as f64 / rate) as u64);
+
+        stats.storage = StorageStats {
+            disk_usage,
+            content,
+            budget,
+            budget_used: (budget > 0).then(|| used as f64 / budget as f64 * 100.0),
+            growth_rate,
+            time_to_full_secs,
+        };
+    }
+
+    fn growth_rate(&self) -> Option<f64> {
+        let (first, first_bytes) = self.samples.front()?;
+        let (last, last_bytes) = self.samples.back()?;
+        let span = last.saturating_sub(*first);
+        if span < MIN_GROWTH_SPAN {
+            return None;
+        }
+        Some((*last_bytes as f64 - *first_bytes as f64) / span as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1000 * BYTES_PER_MB;
+
+    #[test]
+    fn test_projects_time_to_full() {
+        let mut tracker = StorageTracker::default();
+        let mut stats = NodeStats::default();
+        stats.history_data.content_current = 1500.0;
+        stats.state_data.content_current = 250.0;
+
+        // content is used until the data dir has been measured
+        tracker.update(&mut stats, 2000);
+        assert_eq!(stats.storage.disk_usage, None);
+        assert_eq!(stats.storage.content.history, 1500 * BYTES_PER_MB);
+        assert_eq!(stats.storage.budget_used, Some(87.5));
+
+        // 1 GB of 2 GB used, growing by half a MB per second
+        let start = 1_700_000_000;
+        tracker.record(start, GB);
+        tracker.update(&mut stats, 2000);
+        assert_eq!(stats.storage.disk_usage, Some(GB));
+        assert_eq!(stats.storage.budget_used, Some(50.0));
+        assert_eq!(stats.storage.time_to_full_secs, None);
+
+        tracker.record(start + 500, GB + 250 * BYTES_PER_MB);
+        tracker.update(&mut stats, 2000);
+        assert_eq!(stats.storage.time_to_full_secs, None);
+
+        tracker.record(start + 1000, 2 * GB - GB / 2);
+        tracker.update(&mut stats, 2000);
+        assert_eq!(stats.storage.growth_rate, Some(BYTES_PER_MB as f64 / 2.0));
+        assert_eq!(stats.storage.time_to_full_secs, Some(1000));
+    }
+
+    #[test]
+    fn test_no_projection_while_shrinking() {
+        let mut tracker = StorageTracker::default();
+        let mut stats = NodeStats::default();
+        tracker.record(0, 2 * GB);
+        tracker.record(MIN_GROWTH_SPAN, GB);
+        tracker.update(&mut stats, 2000);
+        assert!(stats.storage.growth_rate.unwrap() < 0.0);
+        assert_eq!(stats.storage.time_to_full_secs, None);
+
+        // old samples leave the window
+        tracker.record(GROWTH_WINDOW + MIN_GROWTH_SPAN + 1, GB);
+        tracker.update(&mut stats, 2000);
+        assert_eq!(stats.storage.growth_rate, None);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::types::crash_report::CrashReport;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// decides whether a crashed trin node should be restarted, and how long to wait
+// before doing so. if trin keeps crashing within a short window we stop retrying,
+// since restarting it forever just hides the problem from the user
+#[derive(Debug)]
+pub struct CrashSupervisor {
+    // the number of crashes inside `crash_window` that we consider a crash loop
+    pub max_crashes: usize,
+    pub crash_window: Duration,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    crashes: VecDeque<Instant>,
+    // lifetime counts since the app was started, these survive `reset`
+    total_crashes: u64,
+    restarts: u64,
+}
+
+impl Default for CrashSupervisor {
+    fn default() -> Self {
+        Self {
+            max_crashes: 5,
+            crash_window: Duration::from_secs(10 * 60),
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(60),
+            crashes: VecDeque::new(),
+            total_crashes: 0,
+            restarts: 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SupervisorDecision {
+    // restart the node after waiting for the given delay
+    Restart(Duration),
+    // stop retrying, trin is crash looping
+    CrashLoop(String),
+}
+
+impl CrashSupervisor {
+    pub fn record_crash(&mut self, now: Instant) -> SupervisorDecision {
+        // forget about crashes that happened outside of the window
+        while let Some(crash) = self.crashes.front() {
+            if now.duration_since(*crash) > self.crash_window {
+                self.crashes.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.crashes.push_back(now);
+        self.total_crashes += 1;
+
+        let recent_crashes = self.crashes.len();
+        if recent_crashes >= self.max_crashes {
+            return SupervisorDecision::CrashLoop(format!(
+                "trin crashed {recent_crashes} times within {} seconds",
+                self.crash_window.as_secs()
+            ));
+        }
+        // double the delay for every recent crash: 2s, 4s, 8s, ...
+        let exponent = (recent_crashes - 1).min(16) as u32;
+        let backoff = self.base_backoff.saturating_mul(2u32.pow(exponent));
+        SupervisorDecision::Restart(backoff.min(self.max_backoff))
+    }
+
+    pub fn recent_crashes(&self) -> usize {
+        self.crashes.len()
+    }
+
+    pub fn total_crashes(&self) -> u64 {
+        self.total_crashes
+    }
+
+    pub fn record_restart(&mut self) {
+        self.restarts += 1;
+    }
+
+    pub fn restarts(&self) -> u64 {
+        self.restarts
+    }
+
+    // called whenever the user launches trin by hand, which starts a fresh crash history
+    pub fn reset(&mut self) {
+        self.crashes.clear();
+    }
+}
+
+// payload of the "trin-crashed" event, emitted every time the supervisor restarts trin
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashEvent {
+    pub reason: String,
+    pub restart_in_secs: u64,
+    pub recent_crashes: usize,
+    pub report: CrashReport,
+}
+
+// payload of the "trin-crash-loop" event, emitted once the supervisor gives up
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashLoopEvent {
+    pub reason: String,
+    pub recent_crashes: usize,
+    pub report: CrashReport,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_until_crash_loop() {
+        let mut supervisor = CrashSupervisor::default();
+        let start = Instant::now();
+        let delays: Vec<SupervisorDecision> = (0..5)
+            .map(|i| supervisor.record_crash(start + Duration::from_secs(i)))
+            .collect();
+        assert_eq!(
+            delays[0],
+            SupervisorDecision::Restart(Duration::from_secs(2))
+        );
+        assert_eq!(
+            delays[1],
+            SupervisorDecision::Restart(Duration::from_secs(4))
+        );
+        assert_eq!(
+            delays[2],
+            SupervisorDecision::Restart(Duration::from_secs(8))
+        );
+        assert_eq!(
+            delays[3],
+            SupervisorDecision::Restart(Duration::from_secs(16))
+        );
+        assert!(matches!(delays[4], SupervisorDecision::CrashLoop(_)));
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let mut supervisor = CrashSupervisor {
+            max_crashes: 100,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut last = SupervisorDecision::Restart(Duration::ZERO);
+        for i in 0..20 {
+            last = supervisor.record_crash(start + Duration::from_secs(i));
+        }
+        assert_eq!(last, SupervisorDecision::Restart(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_old_crashes_leave_the_window() {
+        let mut supervisor = CrashSupervisor::default();
+        let start = Instant::now();
+        for i in 0..4 {
+            supervisor.record_crash(start + Duration::from_secs(i));
+        }
+        // the next crash happens long after the previous ones
+        let decision = supervisor.record_crash(start + Duration::from_secs(60 * 60));
+        assert_eq!(
+            decision,
+            SupervisorDecision::Restart(Duration::from_secs(2))
+        );
+        assert_eq!(supervisor.recent_crashes(), 1);
+    }
+}
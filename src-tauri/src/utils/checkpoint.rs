@@ -0,0 +1,186 @@
+use crate::types::checkpoint::{
+    agree_on_genesis_time, parse_genesis_time, BlockHeader, Checkpoint, CheckpointConfig,
+};
+use log::info;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// checkpoint sync providers usually answer within a second, a slow one shouldn't
+// hold up the others for long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// asks every endpoint for its latest finalized block, and returns the one that a majority
+// of them agree on. endpoints finalize at slightly different times, so if they don't agree
+// the oldest finalized slot that was reported is cross-checked instead, since every
+// endpoint should have finalized that one by now. the endpoints also have to agree on
+// their network's genesis time, which the checkpoint's age is computed from
+pub async fn discover_checkpoint(config: &CheckpointConfig) -> Result<Checkpoint, String> {
+    config.validate()?;
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let responses = fetch_all(&client, &config.endpoints, "genesis", parse_genesis_time).await;
+    let genesis_time = agree_on_genesis_time(&responses, config.min_agreement)?;
+
+    let responses = fetch_headers(&client, &config.endpoints, "finalized").await;
+    let result = Checkpoint::agree(&responses, config.min_agreement, genesis_time, now());
+    let oldest_slot = responses
+        .iter()
+        .filter_map(|(_, response)| response.as_ref().ok())
+        .map(|header| header.slot)
+        .min();
+    match (result, oldest_slot) {
+        (Ok(checkpoint), _) => Ok(checkpoint),
+        (Err(e), None) => Err(e),
+        (Err(_), Some(slot)) => {
+            info!("endpoints disagree on the finalized block, cross-checking slot {slot}");
+            let responses = fetch_headers(&client, &config.endpoints, &slot.to_string()).await;
+            Checkpoint::agree(&responses, config.min_agreement, genesis_time, now())
+        }
+    }
+}
+
+async fn fetch_headers(
+    client: &reqwest::Client,
+    endpoints: &[String],
+    block_id: &str,
+) -> Vec<(String, Result<BlockHeader, String>)> {
+    let path = format!("headers/{block_id}");
+    fetch_all(client, endpoints, &path, BlockHeader::parse).await
+}
+
+// asks every endpoint for `/eth/v1/beacon/{path}`
+async fn fetch_all<T: Send + 'static>(
+    client: &reqwest::Client,
+    endpoints: &[String],
+    path: &str,
+    parse: fn(&str) -> Result<T, String>,
+) -> Vec<(String, Result<T, String>)> {
+    // the endpoints are asked at the same time, so one that hangs doesn't slow down the others
+    let requests: Vec<_> = endpoints
+        .iter()
+        .map(|endpoint| {
+            let (client, endpoint, path) = (client.clone(), endpoint.clone(), path.to_string());
+            tauri::async_runtime::spawn(
+                async move { fetch(&client, &endpoint, &path, parse).await },
+            )
+        })
+        .collect();
+    let mut responses = Vec::new();
+    for (endpoint, request) in endpoints.iter().zip(requests) {
+        let response = request.await.unwrap_or_else(|e| Err(e.to_string()));
+        responses.push((endpoint.clone(), response));
+    }
+    responses
+}
+
+async fn fetch<T>(
+    client: &reqwest::Client,
+    endpoint: &str,
+    path: &str,
+    parse: fn(&str) -> Result<T, String>,
+) -> Result<T, String> {
+    let url = format!("{}/eth/v1/beacon/{path}", endpoint.trim_end_matches('/'));
+    let response = client
+        .get(url)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    parse(&response.text().await.map_err(|e| e.to_string())?)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn header_body(root: char, slot: u64) -> String {
+        serde_json::json!({
+            "data": {
+                "root": format!("0x{}", root.to_string().repeat(64)),
+                "header": { "message": { "slot": slot.to_string() } },
+            },
+        })
+        .to_string()
+    }
+
+    const GENESIS_BODY: &str = r#"{"data": {"genesis_time": "1606824023"}}"#;
+
+    // a stand-in for a mainnet beacon node, it serves `finalized` for the finalized header
+    // and `by_slot` for any other header
+    async fn stand_in(finalized: String, by_slot: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0; 1024];
+                let len = stream.read(&mut buf).await.unwrap_or_default();
+                let request = String::from_utf8_lossy(&buf[..len]);
+                let body = if request.starts_with("GET /eth/v1/beacon/genesis ") {
+                    GENESIS_BODY
+                } else if request.starts_with("GET /eth/v1/beacon/headers/finalized ") {
+                    &finalized
+                } else {
+                    &by_slot
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_discover_checkpoint() {
+        let config = CheckpointConfig {
+            endpoints: vec![
+                stand_in(header_body('1', 64), String::new()).await,
+                stand_in(header_body('2', 96), String::new()).await,
+                stand_in(header_body('1', 64), String::new()).await,
+                "http://127.0.0.1:1".to_string(),
+            ],
+            min_agreement: 2,
+        };
+        let checkpoint = discover_checkpoint(&config).await.unwrap();
+        assert_eq!(checkpoint.root, format!("0x{}", "1".repeat(64)));
+        assert_eq!(checkpoint.slot, 64);
+        assert_eq!(checkpoint.sources.len(), 2);
+        assert_eq!(checkpoint.disagreements.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cross_checks_oldest_finalized_slot() {
+        let config = CheckpointConfig {
+            endpoints: vec![
+                stand_in(header_body('1', 64), header_body('1', 64)).await,
+                stand_in(header_body('2', 96), header_body('1', 64)).await,
+                stand_in(header_body('3', 128), header_body('4', 64)).await,
+            ],
+            min_agreement: 2,
+        };
+        let checkpoint = discover_checkpoint(&config).await.unwrap();
+        assert_eq!(checkpoint.root, format!("0x{}", "1".repeat(64)));
+        assert_eq!(checkpoint.epoch, 2);
+        assert_eq!(checkpoint.sources.len(), 2);
+
+        let config = CheckpointConfig {
+            endpoints: config.endpoints[1..].to_vec(),
+            min_agreement: 2,
+        };
+        assert!(discover_checkpoint(&config).await.is_err());
+    }
+}
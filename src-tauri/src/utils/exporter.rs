@@ -0,0 +1,144 @@
+use crate::types::exporter::CONTENT_TYPE;
+use log::{info, warn};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+// scrape requests are tiny, anything bigger than this isn't a scrape
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+// a running metrics exporter, it keeps serving until it is stopped
+pub struct MetricsExporter {
+    port: u16,
+    token: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl MetricsExporter {
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    // waits for the listener to be closed, so that its port can be reused right away
+    pub async fn stop(self) {
+        self.token.cancel();
+        let _ = self.task.await;
+    }
+}
+
+// serves the output of `render` on http://127.0.0.1:{port}/metrics until the exporter
+// is stopped. it only listens on the loopback interface, anyone who wants to scrape
+// it from another machine has to put a proxy in front of it
+pub async fn start_exporter<F>(port: u16, render: F) -> Result<MetricsExporter, String>
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .await
+        .map_err(|e| format!("unable to serve metrics on port {port}: {e}"))?;
+    info!("serving metrics on http://127.0.0.1:{port}/metrics");
+
+    let render = Arc::new(render);
+    let token = CancellationToken::new();
+    let exporter_token = token.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                _ = token.cancelled() => break,
+                accepted = listener.accept() => accepted,
+            };
+            let stream = match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("failed to accept a metrics connection: {e}");
+                    continue;
+                }
+            };
+            let render = render.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = handle_connection(stream, render.as_ref()).await {
+                    warn!("failed to serve metrics: {e}");
+                }
+            });
+        }
+        info!("stopped serving metrics on port {port}");
+    });
+    Ok(MetricsExporter {
+        port,
+        token: exporter_token,
+        task,
+    })
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    render: &(dyn Fn() -> String + Send + Sync),
+) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    // read until the end of the headers, the body of a scrape request is empty
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await;
+        match read {
+            Ok(Ok(0)) => break,
+            Ok(Ok(n)) => request.extend_from_slice(&buf[..n]),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => break,
+        }
+        if request.len() > MAX_REQUEST_BYTES {
+            break;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let request_line = request.lines().next().unwrap_or_default();
+    let (status, content_type, body) = route(request_line, render);
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// returns the status, content type & body of the response to a request
+fn route(
+    request_line: &str,
+    render: &(dyn Fn() -> String + Send + Sync),
+) -> (&'static str, &'static str, String) {
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    // prometheus doesn't send a query string, but other scrapers might
+    let path = path.map(|path| path.split('?').next().unwrap_or_default());
+    match (method, path) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", CONTENT_TYPE, render()),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("GET /metrics HTTP/1.1", "200 OK")]
+    #[case("GET /metrics?name[]=up HTTP/1.1", "200 OK")]
+    #[case("GET / HTTP/1.1", "404 Not Found")]
+    #[case("POST /metrics HTTP/1.1", "405 Method Not Allowed")]
+    #[case("", "405 Method Not Allowed")]
+    fn test_route(#[case] request_line: &str, #[case] expected: &str) {
+        let render = || "# EOF\n".to_string();
+        let (status, _, _) = route(request_line, &render);
+        assert_eq!(status, expected);
+    }
+}
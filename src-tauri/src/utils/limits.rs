@@ -0,0 +1,250 @@
+use crate::types::config::TrinConfig;
+#[cfg(target_os = "linux")]
+use crate::types::config::BYTES_PER_MB;
+use crate::types::limits::AppliedLimits;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+// applies the resource limits from the config to the already spawned trin process.
+// the shell plugin doesn't let us run code between fork and exec, so trin runs
+// without limits for the few milliseconds it takes to apply them
+#[cfg(target_os = "linux")]
+pub fn apply_resource_limits(pid: u32, trin_config: &TrinConfig) -> AppliedLimits {
+    let mut applied = AppliedLimits {
+        pid,
+        ..Default::default()
+    };
+
+    match set_niceness(pid, trin_config.niceness) {
+        Ok(()) => applied.niceness = Some(trin_config.niceness),
+        Err(e) => applied.errors.push(format!("failed to set niceness: {e}")),
+    }
+    match set_io_priority(pid, trin_config.ioPriority) {
+        Ok(()) => applied.io_priority = Some(trin_config.ioPriority),
+        Err(e) => applied
+            .errors
+            .push(format!("failed to set io priority: {e}")),
+    }
+    if let Some(limit) = trin_config.addressSpaceLimit {
+        match set_address_space_limit(pid, limit) {
+            Ok(()) => applied.address_space_limit = Some(limit),
+            Err(e) => applied
+                .errors
+                .push(format!("failed to limit address space: {e}")),
+        }
+    }
+    if trin_config.cgroupCpuQuota.is_some() || trin_config.cgroupMemoryMax.is_some() {
+        match move_to_cgroup(pid, trin_config) {
+            Ok(cgroup) => {
+                applied.cgroup = Some(cgroup);
+                applied.cgroup_cpu_quota = trin_config.cgroupCpuQuota;
+                applied.cgroup_memory_max = trin_config.cgroupMemoryMax;
+            }
+            Err(e) => applied
+                .errors
+                .push(format!("failed to apply cgroup limits: {e}")),
+        }
+    }
+    applied
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply_resource_limits(pid: u32, _trin_config: &TrinConfig) -> AppliedLimits {
+    AppliedLimits {
+        pid,
+        errors: vec!["resource limits are only supported on linux".to_string()],
+        ..Default::default()
+    }
+}
+
+// cleans up after the limits once trin has exited, ie. removes the cgroup it was moved
+// into. a cgroup can't be removed while a process is in it, and a killed process may
+// take a moment to leave it
+#[cfg(target_os = "linux")]
+pub fn release_resource_limits(applied: &AppliedLimits) -> Result<(), String> {
+    let Some(cgroup) = &applied.cgroup else {
+        return Ok(());
+    };
+    let mut attempts = 0;
+    loop {
+        match std::fs::remove_dir(cgroup) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if e.raw_os_error() == Some(libc::EBUSY) && attempts < 20 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            Err(e) => return Err(format!("{}: {e}", cgroup.display())),
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn release_resource_limits(_applied: &AppliedLimits) -> Result<(), String> {
+    Ok(())
+}
+
+// niceness and io priority are per thread on linux, and trin has already started its
+// threads by the time the limits are applied, so they're set on every one of them.
+// threads that are started later inherit them from the thread that starts them
+#[cfg(target_os = "linux")]
+fn for_each_thread(
+    pid: u32,
+    mut apply: impl FnMut(u32) -> std::io::Result<()>,
+) -> Result<(), String> {
+    let tids: Vec<u32> = std::fs::read_dir(format!("/proc/{pid}/task"))
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    for tid in tids {
+        match apply(tid) {
+            // the thread exited in the meantime
+            Err(e) if e.raw_os_error() == Some(libc::ESRCH) => {}
+            Err(e) => return Err(e.to_string()),
+            Ok(()) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_niceness(pid: u32, niceness: i32) -> Result<(), String> {
+    for_each_thread(pid, |tid| {
+        // SAFETY: setpriority doesn't touch any memory owned by us
+        let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, niceness) };
+        if result == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(pid: u32, priority: u8) -> Result<(), String> {
+    // see linux/ioprio.h, libc doesn't define these
+    const IOPRIO_CLASS_BE: libc::c_int = 2;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+    if priority > 7 {
+        return Err(format!(
+            "io priority must be between 0 and 7, got {priority}"
+        ));
+    }
+    let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | priority as libc::c_int;
+    for_each_thread(pid, |tid| {
+        // SAFETY: ioprio_set only takes integer arguments
+        let result =
+            unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, ioprio) };
+        if result == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn set_address_space_limit(pid: u32, megabytes: u64) -> Result<(), String> {
+    let bytes = megabytes.saturating_mul(BYTES_PER_MB);
+    let limit = libc::rlimit {
+        rlim_cur: bytes,
+        rlim_max: bytes,
+    };
+    // SAFETY: `limit` outlives the call, and the old limit isn't requested
+    let result = unsafe {
+        libc::prlimit(
+            pid as libc::pid_t,
+            libc::RLIMIT_AS,
+            &limit,
+            std::ptr::null_mut(),
+        )
+    };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+// creates a "trin" cgroup next to the one the app runs in, which only works if the
+// app's cgroup was delegated to the user, eg. when it's started by a systemd user unit.
+// returns the path of the cgroup
+#[cfg(target_os = "linux")]
+fn move_to_cgroup(pid: u32, trin_config: &TrinConfig) -> Result<PathBuf, String> {
+    let own_cgroup = std::fs::read_to_string("/proc/self/cgroup").map_err(|e| e.to_string())?;
+    let own_cgroup = cgroup_v2_path(&own_cgroup).ok_or("cgroup v2 is not available")?;
+    let parent = Path::new("/sys/fs/cgroup")
+        .join(own_cgroup.trim_start_matches('/'))
+        .parent()
+        .ok_or("the app runs in the root cgroup")?
+        .to_path_buf();
+    // the controllers have to be enabled in the parent, otherwise the limit files
+    // don't exist in the trin cgroup
+    let controllers = [
+        trin_config.cgroupCpuQuota.map(|_| "+cpu"),
+        trin_config.cgroupMemoryMax.map(|_| "+memory"),
+    ];
+    let controllers: Vec<_> = controllers.into_iter().flatten().collect();
+    let subtree_control = parent.join("cgroup.subtree_control");
+    std::fs::write(&subtree_control, controllers.join(" "))
+        .map_err(|e| format!("{}: {e}", subtree_control.display()))?;
+
+    let cgroup = parent.join("trin");
+    std::fs::create_dir_all(&cgroup).map_err(|e| format!("{}: {e}", cgroup.display()))?;
+
+    let write = |file: &str, value: String| {
+        std::fs::write(cgroup.join(file), value).map_err(|e| format!("{file}: {e}"))
+    };
+    if let Some(quota) = trin_config.cgroupCpuQuota {
+        write("cpu.max", cpu_max(quota))?;
+    }
+    if let Some(megabytes) = trin_config.cgroupMemoryMax {
+        write(
+            "memory.max",
+            megabytes.saturating_mul(BYTES_PER_MB).to_string(),
+        )?;
+    }
+    write("cgroup.procs", pid.to_string())?;
+    Ok(cgroup)
+}
+
+// the path of the unified (v2) hierarchy in a /proc/<pid>/cgroup file
+#[cfg(target_os = "linux")]
+fn cgroup_v2_path(proc_cgroup: &str) -> Option<&str> {
+    proc_cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::trim)
+}
+
+// the "cpu.max" value for a quota given as a percentage of a single core
+#[cfg(target_os = "linux")]
+fn cpu_max(quota_percent: u32) -> String {
+    const PERIOD_US: u64 = 100_000;
+    let quota_us = PERIOD_US * quota_percent as u64 / 100;
+    format!("{quota_us} {PERIOD_US}")
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "0::/user.slice/user-1000.slice/app.scope\n",
+        Some("/user.slice/user-1000.slice/app.scope")
+    )]
+    #[case("12:cpu,cpuacct:/user.slice\n0::/user.slice\n", Some("/user.slice"))]
+    #[case("12:cpu,cpuacct:/user.slice\n1:name=systemd:/user.slice\n", None)]
+    fn test_cgroup_v2_path(#[case] proc_cgroup: &str, #[case] expected: Option<&str>) {
+        assert_eq!(cgroup_v2_path(proc_cgroup), expected);
+    }
+
+    #[rstest]
+    #[case(50, "50000 100000")]
+    #[case(100, "100000 100000")]
+    #[case(250, "250000 100000")]
+    fn test_cpu_max(#[case] quota_percent: u32, #[case] expected: &str) {
+        assert_eq!(cpu_max(quota_percent), expected);
+    }
+}
@@ -1 +1,10 @@
+pub mod checkpoint;
+pub mod diagnostics;
+pub mod exporter;
+pub mod limits;
 pub mod node_rpc;
+pub mod ports;
+pub mod probe;
+pub mod process;
+pub mod resources;
+pub mod storage;
\ No newline at end of file
@@ -1,13 +1,69 @@
+use crate::types::metrics::Metrics;
+use crate::types::readiness::count_peers;
+use ethportal_api::jsonrpsee::core::client::ClientT;
 use ethportal_api::jsonrpsee::http_client::HttpClientBuilder;
+use ethportal_api::jsonrpsee::rpc_params;
 use ethportal_api::Web3ApiClient;
+use std::time::Duration;
+use tokio::net::UdpSocket;
 
 // this is the jsonrpc request used to make sure
 // that the trin node is running
 // ... hmm. ok this might not be the best way to check that
 // the trin node is running. eg. the node will respond even if
 // it is not connected to the network (aka error binding to udp socket)
+// so `check_discv5_bound` & `discv5_peer_count` are used to track readiness
 pub async fn check_trin_status(http_port: &usize) -> bool {
     let endpoint = format!("http://localhost:{}", http_port);
     let client = HttpClientBuilder::default().build(&endpoint).unwrap();
     client.client_version().await.is_ok()
 }
+
+// checks that something is listening on the discovery port, without binding to it
+// ourselves (which could steal the port from trin). discv5 silently drops packets it
+// can't decode, but if nothing is listening the os replies with "port unreachable",
+// which shows up as an error when receiving on the connected socket
+pub async fn check_discv5_bound(discovery_port: u16) -> bool {
+    let Ok(socket) = UdpSocket::bind("127.0.0.1:0").await else {
+        return false;
+    };
+    if socket.connect(("127.0.0.1", discovery_port)).await.is_err() {
+        return false;
+    }
+    if socket.send(&[0]).await.is_err() {
+        return false;
+    }
+    let mut buf = [0u8; 64];
+    let response = tokio::time::timeout(Duration::from_millis(500), socket.recv(&mut buf)).await;
+    !matches!(response, Ok(Err(_)))
+}
+
+// the number of peers in trin's discv5 routing table
+pub async fn discv5_peer_count(http_port: &usize) -> Result<usize, String> {
+    let endpoint = format!("http://localhost:{}", http_port);
+    let client = HttpClientBuilder::default()
+        .build(&endpoint)
+        .map_err(|e| e.to_string())?;
+    let routing_table: serde_json::Value = client
+        .request("discv5_routingTableInfo", rpc_params![])
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(count_peers(&routing_table))
+}
+
+// scrapes trin's prometheus metrics endpoint
+pub async fn scrape_metrics(metrics_port: u16) -> Result<Metrics, String> {
+    let endpoint = format!("http://127.0.0.1:{metrics_port}/metrics");
+    let response = reqwest::Client::new()
+        .get(endpoint)
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let metrics = Metrics::parse(&response.text().await.map_err(|e| e.to_string())?);
+    if metrics.is_empty() {
+        return Err("trin didn't export any metrics".to_string());
+    }
+    Ok(metrics)
+}
@@ -0,0 +1,144 @@
+use crate::types::ports::{PortConflict, PortOwner, Protocol};
+use std::net::{TcpListener, UdpSocket};
+
+// the number of free ports suggested for every conflict
+const SUGGESTED_PORTS: usize = 3;
+
+// checks the ports trin is about to bind to. trin binds its http and metrics servers
+// to localhost, but discv5 listens on all interfaces
+pub fn check_trin_ports(
+    http_port: u16,
+    metrics_port: u16,
+    discovery_port: u16,
+) -> Vec<PortConflict> {
+    [
+        ("httpPort", http_port, Protocol::Tcp),
+        ("metricsPort", metrics_port, Protocol::Tcp),
+        ("discoveryPort", discovery_port, Protocol::Udp),
+    ]
+    .into_iter()
+    .filter(|(_, port, protocol)| !is_port_free(*protocol, *port))
+    .map(|(field, port, protocol)| PortConflict {
+        field: field.to_string(),
+        port,
+        protocol,
+        owner: find_port_owner(protocol, port),
+        suggestions: suggest_free_ports(protocol, port),
+    })
+    .collect()
+}
+
+pub fn is_port_free(protocol: Protocol, port: u16) -> bool {
+    match protocol {
+        Protocol::Tcp => TcpListener::bind(("127.0.0.1", port)).is_ok(),
+        Protocol::Udp => UdpSocket::bind(("0.0.0.0", port)).is_ok(),
+    }
+}
+
+// the free ports closest to `port`, skipping the privileged range
+fn suggest_free_ports(protocol: Protocol, port: u16) -> Vec<u16> {
+    (1..=u16::MAX)
+        .flat_map(|distance| [port.checked_add(distance), port.checked_sub(distance)])
+        .flatten()
+        .filter(|candidate| *candidate >= 1024)
+        .take(200)
+        .filter(|candidate| is_port_free(protocol, *candidate))
+        .take(SUGGESTED_PORTS)
+        .collect()
+}
+
+// finding out who owns a socket isn't supported by sysinfo, so on linux we match the
+// socket's inode from /proc/net against the file descriptors of every process
+#[cfg(target_os = "linux")]
+fn find_port_owner(protocol: Protocol, port: u16) -> Option<PortOwner> {
+    use sysinfo::{ProcessesToUpdate, System};
+
+    let tables = match protocol {
+        Protocol::Tcp => ["/proc/net/tcp", "/proc/net/tcp6"],
+        Protocol::Udp => ["/proc/net/udp", "/proc/net/udp6"],
+    };
+    let inodes: Vec<u64> = tables
+        .iter()
+        .filter_map(|table| std::fs::read_to_string(table).ok())
+        .flat_map(|table| socket_inodes(&table, protocol, port))
+        .collect();
+    if inodes.is_empty() {
+        return None;
+    }
+
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    sys.processes().iter().find_map(|(pid, process)| {
+        let fds = std::fs::read_dir(format!("/proc/{pid}/fd")).ok()?;
+        let owns_socket = fds.flatten().any(|fd| {
+            std::fs::read_link(fd.path())
+                .ok()
+                .and_then(|link| {
+                    let link = link.to_str()?;
+                    link.strip_prefix("socket:[")?
+                        .strip_suffix(']')?
+                        .parse()
+                        .ok()
+                })
+                .is_some_and(|inode: u64| inodes.contains(&inode))
+        });
+        owns_socket.then(|| PortOwner {
+            pid: pid.as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+        })
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_port_owner(_protocol: Protocol, _port: u16) -> Option<PortOwner> {
+    None
+}
+
+// the inodes of the sockets bound to `port`, from a /proc/net/{tcp,udp}[6] table
+#[cfg(target_os = "linux")]
+fn socket_inodes(table: &str, protocol: Protocol, port: u16) -> Vec<u64> {
+    // tcp sockets that are listening, and udp sockets that are bound
+    let bound_state = match protocol {
+        Protocol::Tcp => "0A",
+        Protocol::Udp => "07",
+    };
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let local_port = columns.get(1)?.rsplit(':').next()?;
+            let local_port = u16::from_str_radix(local_port, 16).ok()?;
+            let inode: u64 = columns.get(9)?.parse().ok()?;
+            (local_port == port && columns.get(3)? == &bound_state && inode != 0).then_some(inode)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_socket_inodes() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:2161 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 81234 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:2161 0100007F:D4C2 06 00000000:00000000 03:00000F6B 00000000     0        0 0 3 0000000000000000
+   2: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 19876 1 0000000000000000 100 0 0 10 0";
+        assert_eq!(socket_inodes(table, Protocol::Tcp, 8545), vec![81234]);
+        assert_eq!(socket_inodes(table, Protocol::Tcp, 22), vec![19876]);
+        assert!(socket_inodes(table, Protocol::Tcp, 9009).is_empty());
+    }
+
+    #[test]
+    fn test_taken_port_is_reported() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!is_port_free(Protocol::Tcp, port));
+
+        let suggestions = suggest_free_ports(Protocol::Tcp, port);
+        assert_eq!(suggestions.len(), SUGGESTED_PORTS);
+        assert!(!suggestions.contains(&port));
+    }
+}
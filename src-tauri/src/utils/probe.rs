@@ -0,0 +1,34 @@
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+// runs `probe` every `period` until it breaks, or the token is cancelled. every probe
+// gets its own task, so a slow probe only delays its own next run and the others
+// keep their cadence
+pub fn spawn_probe<F, Fut>(token: CancellationToken, period: Duration, mut probe: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ControlFlow<()>> + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            // probes can hang (eg. a request waiting for its timeout), so they're
+            // cancelled mid-flight as well
+            let flow = tokio::select! {
+                _ = token.cancelled() => break,
+                flow = probe() => flow,
+            };
+            if flow.is_break() {
+                break;
+            }
+        }
+    });
+}
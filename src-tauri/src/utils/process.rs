@@ -0,0 +1,91 @@
+use crate::types::pid_file::PidFile;
+use log::warn;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessStatus, ProcessesToUpdate, Signal, System};
+
+// asks the process to shut down by sending it SIGTERM, and waits up to `grace_period`
+// for it to exit. returns false if the process is still alive after the grace period,
+// or if SIGTERM isn't supported on this platform (eg. windows), so the caller can
+// fall back to killing it
+pub async fn terminate_process(pid: u32, grace_period: Duration) -> bool {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    if !is_running(&mut sys, pid) {
+        return true;
+    }
+    let signal_sent = sys
+        .process(pid)
+        .and_then(|process| process.kill_with(Signal::Term));
+    match signal_sent {
+        Some(true) => {}
+        Some(false) => {
+            warn!("failed to send SIGTERM to process {pid}");
+            return false;
+        }
+        None => {
+            warn!("SIGTERM is not supported on this platform");
+            return false;
+        }
+    }
+
+    let deadline = Instant::now() + grace_period;
+    while Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        if !is_running(&mut sys, pid) {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_running(sys: &mut System, pid: Pid) -> bool {
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    match sys.process(pid) {
+        // a zombie has already exited, it's just waiting to be reaped
+        Some(process) => process.status() != ProcessStatus::Zombie,
+        None => false,
+    }
+}
+
+pub fn kill_process(pid: u32) -> bool {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    sys.process(pid)
+        .map(|process| process.kill())
+        .unwrap_or(true)
+}
+
+// seconds since the epoch, used to tell a process apart from a later one with the same pid
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    sys.process(pid).map(|process| process.start_time())
+}
+
+// looks for a trin process left behind by a previous run of the app. the pid file is
+// checked first, otherwise fall back to any process that is running our sidecar binary
+pub fn find_orphaned_trin(pid_file: Option<&PidFile>) -> Option<u32> {
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    if let Some(pid_file) = pid_file {
+        let process = sys.process(Pid::from_u32(pid_file.pid));
+        if process.is_some_and(|process| process.start_time() == pid_file.start_time) {
+            return Some(pid_file.pid);
+        }
+    }
+    let sidecar = sidecar_path()?;
+    sys.processes()
+        .iter()
+        .find(|(_, process)| process.exe() == Some(sidecar.as_path()))
+        .map(|(pid, _)| pid.as_u32())
+}
+
+// tauri places the sidecar next to the app's executable, without the target triple suffix
+fn sidecar_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let name = if cfg!(windows) { "trin.exe" } else { "trin" };
+    Some(exe.parent()?.join(name))
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+// how many samples are taken before looking for new child processes again. finding
+// children requires listing every process on the machine, which is the expensive part
+const TREE_REFRESH_SAMPLES: u32 = 10;
+
+// the resources used by the trin process and all of its descendants
+#[derive(Debug, Default)]
+pub struct ResourceSample {
+    pub cpu: f32,
+    // bytes
+    pub memory: u64,
+    pub virtual_memory: u64,
+    // only available on linux
+    pub threads: Option<usize>,
+    pub open_fds: Option<usize>,
+    // bytes per second since the previous sample
+    pub disk_read_rate: u64,
+    pub disk_write_rate: u64,
+}
+
+// samples the trin process tree, refreshing only the processes that belong to it
+pub struct ProcessTreeSampler {
+    sys: System,
+    root: Pid,
+    tree: Vec<Pid>,
+    samples_until_tree_refresh: u32,
+    last_sample: Option<Instant>,
+}
+
+impl ProcessTreeSampler {
+    pub fn new(pid: u32) -> Self {
+        let root = Pid::from_u32(pid);
+        Self {
+            sys: System::new(),
+            root,
+            tree: vec![root],
+            samples_until_tree_refresh: 0,
+            last_sample: None,
+        }
+    }
+
+    pub fn sample(&mut self) -> ResourceSample {
+        if self.samples_until_tree_refresh == 0 {
+            self.refresh_tree();
+            self.samples_until_tree_refresh = TREE_REFRESH_SAMPLES;
+        }
+        self.samples_until_tree_refresh -= 1;
+
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&self.tree),
+            true,
+            ProcessRefreshKind::new()
+                .with_cpu()
+                .with_memory()
+                .with_disk_usage(),
+        );
+        let now = Instant::now();
+        let elapsed = self
+            .last_sample
+            .replace(now)
+            .map(|last_sample| now.duration_since(last_sample).as_secs_f64());
+
+        let mut sample = ResourceSample::default();
+        let (mut read_bytes, mut written_bytes) = (0, 0);
+        for pid in &self.tree {
+            let Some(process) = self.sys.process(*pid) else {
+                continue;
+            };
+            sample.cpu += process.cpu_usage();
+            sample.memory += process.memory();
+            sample.virtual_memory += process.virtual_memory();
+            if let Some(tasks) = process.tasks() {
+                *sample.threads.get_or_insert(0) += tasks.len().max(1);
+            }
+            if let Some(open_fds) = count_open_fds(*pid) {
+                *sample.open_fds.get_or_insert(0) += open_fds;
+            }
+            let disk_usage = process.disk_usage();
+            read_bytes += disk_usage.read_bytes;
+            written_bytes += disk_usage.written_bytes;
+        }
+        // the disk usage of the first sample covers the whole lifetime of the process
+        if let Some(elapsed) = elapsed.filter(|elapsed| *elapsed > 0.0) {
+            sample.disk_read_rate = (read_bytes as f64 / elapsed) as u64;
+            sample.disk_write_rate = (written_bytes as f64 / elapsed) as u64;
+        }
+        sample
+    }
+
+    fn refresh_tree(&mut self) {
+        let mut sys = System::new();
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::new());
+        // threads are listed as processes on linux, they're counted through their process
+        let parents = sys
+            .processes()
+            .iter()
+            .filter(|(_, process)| process.thread_kind().is_none())
+            .map(|(pid, process)| (*pid, process.parent()));
+        self.tree = process_tree(self.root, parents);
+    }
+}
+
+// the root process followed by all of its descendants
+fn process_tree(root: Pid, parents: impl Iterator<Item = (Pid, Option<Pid>)>) -> Vec<Pid> {
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, parent) in parents {
+        if let Some(parent) = parent {
+            children.entry(parent).or_default().push(pid);
+        }
+    }
+    let mut tree = vec![root];
+    let mut i = 0;
+    while let Some(pid) = tree.get(i) {
+        if let Some(children) = children.get(pid) {
+            tree.extend(children.iter().filter(|child| **child != root));
+        }
+        i += 1;
+    }
+    tree
+}
+
+#[cfg(target_os = "linux")]
+fn count_open_fds(pid: Pid) -> Option<usize> {
+    std::fs::read_dir(format!("/proc/{pid}/fd"))
+        .ok()
+        .map(|fds| fds.count())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count_open_fds(_pid: Pid) -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_tree_includes_grandchildren() {
+        let pid = Pid::from_u32;
+        let parents = [
+            (pid(1), None),
+            (pid(10), Some(pid(1))),
+            (pid(11), Some(pid(10))),
+            (pid(12), Some(pid(11))),
+            (pid(13), Some(pid(10))),
+            (pid(20), Some(pid(1))),
+        ];
+        let mut tree = process_tree(pid(10), parents.into_iter());
+        assert_eq!(tree[0], pid(10));
+        tree.sort();
+        assert_eq!(tree, vec![pid(10), pid(11), pid(12), pid(13)]);
+    }
+}
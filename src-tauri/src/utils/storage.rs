@@ -0,0 +1,75 @@
+use std::fs::DirEntry;
+use std::io;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+// trin reads its data dir from this environment variable, before falling back to its default
+const TRIN_DATA_PATH: &str = "TRIN_DATA_PATH";
+
+// the data dir trin uses when it isn't told otherwise, `local_data_dir` is the
+// platform's local data dir (eg. ~/.local/share on linux)
+pub fn default_trin_data_dir(local_data_dir: Option<PathBuf>) -> Option<PathBuf> {
+    match std::env::var_os(TRIN_DATA_PATH) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => local_data_dir.map(|dir| dir.join("trin")),
+    }
+}
+
+// the total size of the files in a directory and its subdirectories, in bytes.
+// symlinks aren't followed, so nothing is counted twice
+pub fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        // trin creates & removes files while it runs, one that is gone by the time
+        // it is looked at doesn't take up any space
+        match entry.and_then(|entry| entry_size(&entry)) {
+            Ok(entry_size) => size += entry_size,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(size)
+}
+
+fn entry_size(entry: &DirEntry) -> io::Result<u64> {
+    let file_type = entry.file_type()?;
+    if file_type.is_dir() {
+        dir_size(&entry.path())
+    } else if file_type.is_file() {
+        Ok(entry.metadata()?.len())
+    } else {
+        Ok(0)
+    }
+}
+
+// the free space on the disk that holds `path`, which doesn't have to exist yet
+pub fn available_space(path: &Path) -> Option<u64> {
+    let path = path
+        .ancestors()
+        .find_map(|ancestor| ancestor.canonicalize().ok())?;
+    let disks = Disks::new_with_refreshed_list();
+    // the disk mounted closest to the path is the one it's stored on
+    disks
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+        .map(|disk| disk.available_space())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_size() {
+        let dir = std::env::temp_dir().join("trin-desktop-storage-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("history")).unwrap();
+        std::fs::write(dir.join("trin.sqlite"), vec![0; 1000]).unwrap();
+        std::fs::write(dir.join("history").join("content.sqlite"), vec![0; 234]).unwrap();
+
+        assert_eq!(dir_size(&dir).unwrap(), 1234);
+        assert!(dir_size(&dir.join("missing")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,7 @@
+export const formatDuration = (seconds) => {
+  const days = Math.floor(seconds / 86400)
+  if (days > 0) return `${days}d ${Math.floor((seconds % 86400) / 3600)}h`
+  const hours = Math.floor(seconds / 3600)
+  if (hours > 0) return `${hours}h ${Math.floor((seconds % 3600) / 60)}m`
+  return `${Math.max(1, Math.floor(seconds / 60))}m`
+}
@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { ref } from 'vue'
+
+const alertHistory = ref([])
+
+export function useAlerts() {
+  // native notifications are shown by the backend, this only keeps the history current
+  listen('trin-alert', (alert) => {
+    alertHistory.value.push(alert.payload)
+  })
+
+  async function loadAlertHistory() {
+    alertHistory.value = await invoke('get_alert_history')
+  }
+
+  async function clearAlertHistory() {
+    await invoke('clear_alert_history')
+    alertHistory.value = []
+  }
+
+  // each rule: { id, name, enabled, condition: { kind, ... }, forSecs, cooldownSecs }
+  async function getAlertRules() {
+    return await invoke('get_alert_rules')
+  }
+
+  async function setAlertRules(rules) {
+    await invoke('set_alert_rules', { rules })
+  }
+
+  return {
+    alertHistory,
+    loadAlertHistory,
+    clearAlertHistory,
+    getAlertRules,
+    setAlertRules
+  }
+}
@@ -0,0 +1,50 @@
+import { invoke } from '@tauri-apps/api/core'
+import { ref } from 'vue'
+import { useTrinConfig } from './useTrinConfig'
+
+const activeProfile = ref('default')
+// each profile: { name, config }, where config is a trin config
+const profiles = ref([])
+
+export function useProfiles() {
+  const { initializeConfig } = useTrinConfig()
+
+  async function loadProfiles() {
+    const list = await invoke('get_profiles')
+    activeProfile.value = list.active
+    profiles.value = list.profiles
+  }
+
+  // a profile without a dataDir gets a data dir of its own
+  async function createProfile(name, trinConfig) {
+    await invoke('create_profile', { name, trinConfig })
+    await loadProfiles()
+  }
+
+  async function cloneProfile(from, name) {
+    await invoke('clone_profile', { from, name })
+    await loadProfiles()
+  }
+
+  // the profile's data dir is kept on disk
+  async function deleteProfile(name) {
+    await invoke('delete_profile', { name })
+    await loadProfiles()
+  }
+
+  // restarts trin with the profile's config if it is running
+  async function activateProfile(name) {
+    await invoke('activate_profile', { name })
+    await Promise.all([loadProfiles(), initializeConfig()])
+  }
+
+  return {
+    activeProfile,
+    profiles,
+    loadProfiles,
+    createProfile,
+    cloneProfile,
+    deleteProfile,
+    activateProfile
+  }
+}
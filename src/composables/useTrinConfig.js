@@ -1,43 +1,48 @@
 import { useToast } from '@/components/ui/toast'
-import { disable, enable } from '@tauri-apps/plugin-autostart'
-import { load } from '@tauri-apps/plugin-store'
+import { invoke } from '@tauri-apps/api/core'
 import { ref } from 'vue'
 
 // these values don't matter, they will be overwritten by initializeConfig
 const config = ref({
   storage: 2000,
   httpPort: 8545,
+  // launch the app on login, and trin when the app launches
   autostart: true,
-  trustedBlockRoot: '0x'
+  autostartNode: true,
+  trustedBlockRoot: '0x',
+  orphanedNodePolicy: 'adopt',
+  metricsPort: 9100,
+  // local OpenMetrics exporter of the app's own stats, served on 127.0.0.1
+  metricsExporter: { enabled: false, port: 9101 },
+  // beacon api endpoints that trusted block roots are discovered from, at least
+  // minAgreement of them have to report the same root
+  checkpointSync: { endpoints: [], minAgreement: 2 },
+  // resource limits, only applied on linux. null means no limit
+  niceness: 10,
+  ioPriority: 7,
+  addressSpaceLimit: null,
+  cgroupCpuQuota: null,
+  cgroupMemoryMax: null,
+  // trin's command line, validated by the backend before launching
+  subnetworks: ['history', 'state', 'beacon'],
+  discoveryPort: 9009,
+  externalAddress: null,
+  // { kind: 'default' }, { kind: 'none' } or { kind: 'custom', enrs: [...] }
+  bootnodes: { kind: 'default' },
+  dataDir: null,
+  // 'mainnet' or 'testnet'
+  network: 'mainnet',
+  logFilter: null
 })
 
 export function useTrinConfig() {
   const { toast } = useToast()
 
+  // the backend owns config.json, it also enables autostart and the metrics exporter
   async function updateConfig(values) {
     try {
-      const store = await load('config.json', { autoSave: true })
-      if (typeof values.storage !== 'undefined') {
-        config.value.storage = values.storage
-        await store.set('storage', config.value.storage)
-      }
-      if (typeof values.httpPort !== 'undefined') {
-        config.value.httpPort = values.httpPort
-        await store.set('httpPort', config.value.httpPort)
-      }
-      if (typeof values.trustedBlockRoot !== 'undefined') {
-        config.value.trustedBlockRoot = values.trustedBlockRoot
-        await store.set('trustedBlockRoot', config.value.trustedBlockRoot)
-      }
-      if (typeof values.autostart !== 'undefined') {
-        if (values.autostart) {
-          await enable()
-        } else {
-          await disable()
-        }
-        config.value.autostart = values.autostart
-        await store.set('autostart', config.value.autostart)
-      }
+      await invoke('set_config', { config: { ...config.value, ...values } })
+      Object.assign(config.value, values)
       toast({ title: 'Configuration updated successfully.' })
     } catch (e) {
       toast({
@@ -49,32 +54,25 @@ export function useTrinConfig() {
   }
 
   async function initializeConfig() {
-    console.log('initializeConfig')
-    const store = await load('config.json', { autoSave: true })
-    console.log('config initialized')
-    const httpPort = await store.get('httpPort')
-
-    if (!httpPort) {
-      store.set('httpPort', 8545)
-      store.set('storage', 2000)
-      store.set('autostart', true)
-      store.set('trustedBlockRoot', '0x')
-      config.value.httpPort = 8545
-      config.value.storage = 2000
-      config.value.autostart = true
-      config.value.trustedBlockRoot = '0x'
-    } else {
-      config.value.httpPort = httpPort
-      config.value.storage = await store.get('storage')
-      config.value.autostart = await store.get('autostart')
-      config.value.trustedBlockRoot = await store.get('trustedBlockRoot')
-    }
+    Object.assign(config.value, await invoke('get_config'))
     return config.value
   }
 
+  // returns a list of { field, code, message } errors, empty when the config can be launched
+  async function validateConfig(values = {}) {
+    return await invoke('validate_config', { trinConfig: { ...config.value, ...values } })
+  }
+
+  // returns { root, slot, epoch, ageSecs, sources, disagreements }, it isn't saved
+  async function discoverTrustedBlockRoot() {
+    return await invoke('discover_trusted_block_root')
+  }
+
   return {
     config,
     updateConfig,
-    initializeConfig
+    initializeConfig,
+    validateConfig,
+    discoverTrustedBlockRoot
   }
 }
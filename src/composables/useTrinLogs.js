@@ -0,0 +1,40 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { ref } from 'vue'
+
+// the number of live records kept for the tail view
+const TAIL_RECORDS = 500
+
+const tail = ref([])
+
+export function useTrinLogs() {
+  // the backend sends the new records in batches
+  listen('trin-log', (event) => {
+    tail.value.push(...event.payload)
+    if (tail.value.length > TAIL_RECORDS) {
+      tail.value.splice(0, tail.value.length - TAIL_RECORDS)
+    }
+  })
+
+  // query: { minLevel, target, search, before, limit }, all optional.
+  // returns { records, nextBefore }, pass nextBefore as before to get older records
+  async function getLogs(query = {}) {
+    return await invoke('get_trin_logs', { query })
+  }
+
+  async function getLogTargets() {
+    return await invoke('get_trin_log_targets')
+  }
+
+  async function clearLogs() {
+    await invoke('clear_trin_logs')
+    tail.value = []
+  }
+
+  return {
+    tail,
+    getLogs,
+    getLogTargets,
+    clearLogs
+  }
+}
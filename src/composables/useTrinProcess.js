@@ -8,6 +8,11 @@ const { config } = useTrinConfig()
 const trinStatus = ref('stopped')
 const isLaunching = ref(false)
 
+// running and degraded nodes both have a live sidecar
+function toTrinStatus(nodeState) {
+  return nodeState === 'running' || nodeState === 'degraded' ? 'running' : 'stopped'
+}
+
 export function useTrinProcess() {
   const { toast } = useToast()
 
@@ -23,7 +28,6 @@ export function useTrinProcess() {
     isLaunching.value = true
     try {
       await invoke('launch_trin', { trinConfig: config })
-      trinStatus.value = 'running'
     } catch (e) {
       toast({
         title: 'Failed to launch Trin.',
@@ -38,7 +42,6 @@ export function useTrinProcess() {
     isLaunching.value = true
     try {
       await invoke('shutdown_trin')
-      trinStatus.value = 'stopped'
     } catch (e) {
       toast({
         title: 'Failed to shutdown Trin.',
@@ -49,14 +52,34 @@ export function useTrinProcess() {
     isLaunching.value = false
   }
 
-  // Set up crash listener
-  listen('trin-crashed', () => {
-    trinStatus.value = 'stopped'
+  // the backend owns the node state, so it survives reloading the webview
+  invoke('get_node_status').then((status) => {
+    trinStatus.value = toTrinStatus(status.state)
+  })
+
+  listen('trin-node-state', (event) => {
+    trinStatus.value = toTrinStatus(event.payload.state)
+  })
+
+  // Set up crash listeners, restarting the node is handled by the backend
+  listen('trin-crashed', (event) => {
     toast({
       title: 'Trin process has crashed! Restarting your node.',
+      description: `Restarting in ${event.payload.restartInSecs} seconds.`,
+      variant: 'destructive'
+    })
+  })
+
+  listen('trin-restarted', () => {
+    toast({ title: 'Trin process has been restarted.' })
+  })
+
+  listen('trin-crash-loop', (event) => {
+    toast({
+      title: 'Trin keeps crashing, no longer restarting your node.',
+      description: event.payload.reason + ': ' + event.payload.report.reason,
       variant: 'destructive'
     })
-    launchTrin()
   })
 
   return {
@@ -1,12 +1,41 @@
+import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { ref } from 'vue'
 
 const trinStats = ref({
   cpu: 0,
   pid: 0,
+  memory: 0,
+  virtualMemory: 0,
+  threads: null,
+  openFds: null,
+  diskReadRate: 0,
+  diskWriteRate: 0,
+  // in MB
   diskUsage: 0,
+  // sizes are in bytes, budgetUsed is a percentage of the configured storage
+  storage: {
+    diskUsage: null,
+    content: { state: 0, history: 0, beacon: 0 },
+    budget: 0,
+    budgetUsed: null,
+    growthRate: null,
+    timeToFullSecs: null
+  },
   latestFinalizedBlock: 0,
   latestOptimisticBlock: 0,
+  logParseFailures: 0,
+  // 'metrics' or 'logs', depending on where the subnetwork data comes from
+  statsSource: 'logs',
+  network: {
+    connectedPeers: null,
+    messagesSent: 0,
+    messagesReceived: 0,
+    bytesSent: 0,
+    bytesReceived: 0,
+    activeStreams: 0,
+    lookupLatencyMs: null
+  },
   state: {
     radius: 0,
     contentCurrent: 0,
@@ -50,10 +79,24 @@ export function useTrinStats() {
     Object.assign(trinStats.value, {
       cpu: stats.payload.cpu,
       pid: stats.payload.pid,
-      // using state data here, though history should return the same value
-      diskUsage: stats.payload.stateData.disk_usage,
+      memory: stats.payload.memory,
+      virtualMemory: stats.payload.virtualMemory,
+      threads: stats.payload.threads,
+      openFds: stats.payload.openFds,
+      diskReadRate: stats.payload.diskReadRate,
+      diskWriteRate: stats.payload.diskWriteRate,
+      // measured from trin's data dir, until then the database size trin reports
+      // with every subnetwork is used
+      diskUsage:
+        stats.payload.storage.diskUsage !== null
+          ? stats.payload.storage.diskUsage / 1e6
+          : stats.payload.stateData.disk_usage,
+      storage: stats.payload.storage,
       latestFinalizedBlock: stats.payload.latestFinalizedBlock,
       latestOptimisticBlock: stats.payload.latestOptimisticBlock,
+      logParseFailures: stats.payload.logParseFailures,
+      statsSource: stats.payload.statsSource,
+      network: stats.payload.network,
       state: {
         radius: stats.payload.stateData.radius,
         contentCurrent: stats.payload.stateData.content_current,
@@ -64,7 +107,9 @@ export function useTrinStats() {
         acceptsIn: stats.payload.stateData.accepts_in,
         acceptsOut: stats.payload.stateData.accepts_out,
         validationsIn: stats.payload.stateData.validations_in,
-        validationsOut: stats.payload.stateData.validations_out
+        validationsOut: stats.payload.stateData.validations_out,
+        // per second rates, deltas and lifetime totals of the counters above
+        activity: stats.payload.stateActivity
       },
       history: {
         radius: stats.payload.historyData.radius,
@@ -76,7 +121,9 @@ export function useTrinStats() {
         acceptsIn: stats.payload.historyData.accepts_in,
         acceptsOut: stats.payload.historyData.accepts_out,
         validationsIn: stats.payload.historyData.validations_in,
-        validationsOut: stats.payload.historyData.validations_out
+        validationsOut: stats.payload.historyData.validations_out,
+        // per second rates, deltas and lifetime totals of the counters above
+        activity: stats.payload.historyActivity
       },
       beacon: {
         radius: stats.payload.beaconData.radius,
@@ -88,12 +135,21 @@ export function useTrinStats() {
         acceptsIn: stats.payload.beaconData.accepts_in,
         acceptsOut: stats.payload.beaconData.accepts_out,
         validationsIn: stats.payload.beaconData.validations_in,
-        validationsOut: stats.payload.beaconData.validations_out
+        validationsOut: stats.payload.beaconData.validations_out,
+        // per second rates, deltas and lifetime totals of the counters above
+        activity: stats.payload.beaconActivity
       }
     })
   })
 
+  // metric is the path of a field in the stats event, eg. 'historyData.radius'.
+  // from & to are unix timestamps in seconds, resolution is 'raw', 'minute' or 'hour'
+  async function getStatsHistory(metric, from, to, resolution = null) {
+    return await invoke('get_stats_history', { metric, from, to, resolution })
+  }
+
   return {
-    trinStats
+    trinStats,
+    getStatsHistory
   }
 }